@@ -0,0 +1,67 @@
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::program::Program;
+use std::time::Instant;
+
+const STEPS: u32 = 10_000_000;
+
+// An endless loop of arithmetic, calls and skips that never waits on keys or draws
+const ROM: [u8; 24] = [
+    0x60, 0x00, // 200: LD V0, 0
+    0x61, 0x00, // 202: LD V1, 0
+    0x70, 0x01, // 204: ADD V0, 1
+    0x81, 0x04, // 206: ADD V1, V0
+    0x82, 0x03, // 208: XOR V2, V0
+    0x22, 0x12, // 20A: CALL 212
+    0x30, 0x00, // 20C: SE V0, 0
+    0x12, 0x04, // 20E: JP 204
+    0x12, 0x00, // 210: JP 200
+    0xA3, 0x00, // 212: LD I, 300
+    0xF2, 0x1E, // 214: ADD I, V2
+    0x00, 0xEE, // 216: RET
+];
+
+fn run(name: &str, mut interpreter: Interpreter) -> Interpreter {
+    interpreter.load_program(&Program::from(ROM.to_vec())).unwrap();
+
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        interpreter.step();
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<8} {} steps in {:?} : {:.1} M steps/s",
+        name,
+        STEPS,
+        elapsed,
+        STEPS as f64 / elapsed.as_secs_f64() / 1e6
+    );
+    interpreter
+}
+
+fn main() {
+    let plain = run("decode", Interpreter::new());
+    let cached = run("cached", Interpreter::with_cache());
+
+    assert_eq!(plain.cpu.registers, cached.cpu.registers);
+    assert_eq!(plain.cpu.program_counter, cached.cpu.program_counter);
+
+    #[cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+    {
+        let mut jit = chip8::chip8::jit::Jit::new();
+        jit.load_program(&Program::from(ROM.to_vec())).unwrap();
+
+        let start = Instant::now();
+        jit.run(STEPS as u64);
+        let elapsed = start.elapsed();
+
+        println!(
+            "{:<8} {} steps in {:?} : {:.1} M steps/s",
+            "jit",
+            STEPS,
+            elapsed,
+            STEPS as f64 / elapsed.as_secs_f64() / 1e6
+        );
+        assert_eq!(plain.cpu.registers, jit.interpreter.cpu.registers);
+    }
+}
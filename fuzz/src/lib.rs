@@ -0,0 +1,97 @@
+use chip8::chip8::interpreter::Interpreter;
+#[cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+use chip8::chip8::jit::Jit;
+use chip8::chip8::program::Program;
+
+// The input starts with the keys held during each slice of the run, one
+// 16 bit mask per slice, the rest is the rom
+const SLICES: usize = 16;
+const HEADER: usize = 2 * SLICES;
+const STEPS_PER_SLICE: usize = 200;
+const MAX_ROM: usize = 4096 - 0x200;
+const SEED: u64 = 0xC8;
+
+fn assert_same(name: &str, a: &Interpreter, b: &Interpreter) {
+    let (a, b) = (&a.cpu, &b.cpu);
+    assert_eq!(a.program_counter, b.program_counter, "{}: PC", name);
+    assert_eq!(a.registers, b.registers, "{}: registers", name);
+    assert_eq!(a.index, b.index, "{}: I", name);
+    assert_eq!(a.stack_pointer, b.stack_pointer, "{}: SP", name);
+    assert_eq!(a.stack, b.stack, "{}: stack", name);
+    assert_eq!(a.delay_timer, b.delay_timer, "{}: DT", name);
+    assert_eq!(a.sound_timer, b.sound_timer, "{}: ST", name);
+    assert!(a.memory == b.memory, "{}: memory", name);
+    assert_eq!(
+        a.display.to_string(),
+        b.display.to_string(),
+        "{}: display",
+        name
+    );
+}
+
+fn load(interpreter: &mut Interpreter, program: &Program) {
+    interpreter.seed_rng(SEED);
+    interpreter.load_program(program).unwrap();
+}
+
+// Runs the rom on the plain interpreter through the error-returning API, which
+// must never panic, then makes every other backend execute the same number of
+// instructions and compares the states
+pub fn differential(data: &[u8]) {
+    let split = data.len().min(HEADER);
+    let (header, rom) = data.split_at(split);
+    let program = Program::from(rom[..rom.len().min(MAX_ROM)].to_vec());
+
+    let mut plain = Interpreter::new();
+    load(&mut plain, &program);
+    let mut cached = Interpreter::with_cache();
+    load(&mut cached, &program);
+    #[cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+    let mut jit = {
+        let mut jit = Jit::new();
+        jit.interpreter.seed_rng(SEED);
+        jit.load_program(&program).unwrap();
+        jit
+    };
+
+    for slice in 0..SLICES {
+        let mask = header
+            .get(2 * slice..2 * slice + 2)
+            .map_or(0, |b| u16::from_le_bytes([b[0], b[1]]));
+        let keys: [bool; 16] = std::array::from_fn(|k| mask & (1 << k) != 0);
+        plain.cpu.keys = keys;
+        cached.cpu.keys = keys;
+        #[cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+        {
+            jit.interpreter.cpu.keys = keys;
+        }
+
+        let mut steps = 0;
+        let mut failed = false;
+        while steps < STEPS_PER_SLICE {
+            if plain.try_step().is_err() {
+                failed = true;
+                break;
+            }
+            steps += 1;
+        }
+
+        for _ in 0..steps {
+            cached.step();
+        }
+        assert_same("cached", &plain, &cached);
+        #[cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+        {
+            jit.run(steps as u64);
+            assert_same("jit", &plain, &jit.interpreter);
+        }
+
+        if failed {
+            return;
+        }
+        plain.tick_timers();
+        cached.tick_timers();
+        #[cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+        jit.interpreter.tick_timers();
+    }
+}
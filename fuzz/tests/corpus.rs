@@ -0,0 +1,25 @@
+// Replays the checked-in seeds and a batch of random inputs without libFuzzer,
+// so the harness also runs offline with a plain `cargo test`
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn seeds() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus/differential");
+    for entry in fs::read_dir(dir).unwrap() {
+        let data = fs::read(entry.unwrap().path()).unwrap();
+        chip8_fuzz::differential(&data);
+    }
+}
+
+#[test]
+fn random_inputs() {
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..2000 {
+        let len = rng.gen_range(0..512);
+        let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+        chip8_fuzz::differential(&data);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::chip8::program::{LoadError, Program, MEMORY_SIZE};
+use flate2::read::GzDecoder;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+// The extensions of the rom files, the other files of a directory are
+// skipped but for the archives
+pub const EXTENSIONS: [&str; 6] = ["ch8", "c8", "chip8", "sc8", "xo8", "rom"];
+const ARCHIVES: [&str; 2] = ["zip", "gz"];
+
+// A rom found in a file, an archive or a directory
+pub struct Rom {
+    // the path in the archive or the file name
+    pub name: String,
+    pub program: Program,
+}
+
+// The roms of a file, a .zip or .gz archive (told by their content) or a
+// directory, read in memory without extracting anything. In archives and
+// directories the files too large for the memory are skipped, directories
+// only give the files with the extension of a rom or an archive
+pub fn open(path: impl AsRef<Path>) -> Result<Vec<Rom>, LoadError> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .map_or(String::new(), |n| n.to_string_lossy().into_owned());
+    if !path.is_dir() {
+        return read(&name, &std::fs::read(path)?);
+    }
+
+    let mut files: Vec<_> = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    files.sort();
+    let mut roms = Vec::new();
+    for file in files {
+        let hidden = file
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        let known = has_extension(&file, &EXTENSIONS) || has_extension(&file, &ARCHIVES);
+        if hidden || !known || file.is_dir() {
+            continue;
+        }
+        match open(&file) {
+            Ok(found) => roms.extend(found),
+            Err(LoadError::TooLarge { .. } | LoadError::NoRom) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    non_empty(roms)
+}
+
+// Whether the name ends with one of `extensions`, whatever the case
+pub fn has_extension(name: impl AsRef<Path>, extensions: &[&str]) -> bool {
+    name.as_ref()
+        .extension()
+        .is_some_and(|e| extensions.iter().any(|x| e.eq_ignore_ascii_case(x)))
+}
+
+// The roms of the content of a file named `name`
+pub fn read(name: &str, bytes: &[u8]) -> Result<Vec<Rom>, LoadError> {
+    match bytes {
+        [b'P', b'K', 3, 4, ..] => read_zip(bytes),
+        [0x1F, 0x8B, ..] => {
+            let name = name.strip_suffix(".gz").unwrap_or(name).to_string();
+            let program = Program::from_reader(GzDecoder::new(bytes))?;
+            Ok(vec![Rom { name, program }])
+        }
+        _ => Ok(vec![Rom {
+            name: name.to_string(),
+            program: Program::from_bytes(bytes)?,
+        }]),
+    }
+}
+
+fn read_zip(bytes: &[u8]) -> Result<Vec<Rom>, LoadError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(std::io::Error::from)?;
+    let mut roms = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(std::io::Error::from)?;
+        if file.is_dir() || file.size() > MEMORY_SIZE as u64 {
+            continue;
+        }
+        let mut content = Vec::new();
+        file.by_ref()
+            .take(MEMORY_SIZE as u64)
+            .read_to_end(&mut content)?;
+        match Program::from_bytes(&content) {
+            Ok(program) => roms.push(Rom {
+                name: file.name().to_string(),
+                program,
+            }),
+            Err(LoadError::TooLarge { .. }) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    non_empty(roms)
+}
+
+fn non_empty(roms: Vec<Rom>) -> Result<Vec<Rom>, LoadError> {
+    if roms.is_empty() {
+        return Err(LoadError::NoRom);
+    }
+    Ok(roms)
+}
@@ -0,0 +1,185 @@
+use crate::chip8::instructions::Instruction;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+// A line that does not assemble, with the reason
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub text: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot assemble \"{}\" : {}", self.text, self.reason)
+    }
+}
+
+impl Error for ParseError {}
+
+// Hexadecimal like the disassembler output, 0x, # and $ prefixes are accepted
+fn number(operand: &str, max: u16) -> Result<u16, &'static str> {
+    let digits = operand
+        .strip_prefix("0X")
+        .or_else(|| operand.strip_prefix('#'))
+        .or_else(|| operand.strip_prefix('$'))
+        .unwrap_or(operand);
+    let n = u16::from_str_radix(digits, 16).map_err(|_| "expected a hexadecimal number")?;
+    if n > max {
+        return Err("number out of range");
+    }
+    Ok(n)
+}
+
+fn register(operand: &str) -> Option<u8> {
+    let digit = operand.strip_prefix('V')?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+fn vx(operand: &str) -> Result<u8, &'static str> {
+    register(operand).ok_or("expected a register V0 to VF")
+}
+
+fn addr(operand: &str) -> Result<u16, &'static str> {
+    number(operand, 0xFFF)
+}
+
+fn byte(operand: &str) -> Result<u8, &'static str> {
+    number(operand, 0xFF).map(|n| n as u8)
+}
+
+// Parses the syntax of the disassembler and of `Instruction`'s Display, case insensitive
+fn parse(text: &str) -> Result<Instruction, &'static str> {
+    let text = text.trim().to_ascii_uppercase();
+    let (mnemonic, rest) = text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+    let operands: Vec<&str> = match rest.trim() {
+        "" => Vec::new(),
+        rest => rest.split(',').map(str::trim).collect(),
+    };
+
+    let instruction = match (mnemonic, operands.as_slice()) {
+        ("CLS", []) => Instruction::Cls,
+        ("RET", []) => Instruction::Ret,
+        ("SYS", [a]) => Instruction::SysAddr { addr: addr(a)? },
+        ("JP", [a]) => Instruction::JpAddr { addr: addr(a)? },
+        ("JP", ["V0", a]) => Instruction::JpV0Addr { addr: addr(a)? },
+        ("CALL", [a]) => Instruction::CallAddr { addr: addr(a)? },
+        ("SE", [x, y]) => match register(y) {
+            Some(y) => Instruction::SeVxVy { x: vx(x)?, y },
+            None => Instruction::SeVxByte {
+                x: vx(x)?,
+                byte: byte(y)?,
+            },
+        },
+        ("SNE", [x, y]) => match register(y) {
+            Some(y) => Instruction::SneVxVy { x: vx(x)?, y },
+            None => Instruction::SneVxByte {
+                x: vx(x)?,
+                byte: byte(y)?,
+            },
+        },
+        ("ADD", ["I", x]) => Instruction::AddIVx { x: vx(x)? },
+        ("ADD", [x, y]) => match register(y) {
+            Some(y) => Instruction::AddVxVy { x: vx(x)?, y },
+            None => Instruction::AddVxByte {
+                x: vx(x)?,
+                byte: byte(y)?,
+            },
+        },
+        ("OR", [x, y]) => Instruction::OrVxVy {
+            x: vx(x)?,
+            y: vx(y)?,
+        },
+        ("AND", [x, y]) => Instruction::AndVxVy {
+            x: vx(x)?,
+            y: vx(y)?,
+        },
+        ("XOR", [x, y]) => Instruction::XorVxVy {
+            x: vx(x)?,
+            y: vx(y)?,
+        },
+        ("SUB", [x, y]) => Instruction::SubVxVy {
+            x: vx(x)?,
+            y: vx(y)?,
+        },
+        ("SHR", [x, y]) => Instruction::ShrVxVy {
+            x: vx(x)?,
+            y: vx(y)?,
+        },
+        ("SUBN", [x, y]) => Instruction::SubnVxVy {
+            x: vx(x)?,
+            y: vx(y)?,
+        },
+        ("SHL", [x, y]) => Instruction::ShlVxVy {
+            x: vx(x)?,
+            y: vx(y)?,
+        },
+        ("LD", ["I", a]) => Instruction::LdIAddr { addr: addr(a)? },
+        ("LD", ["[I]", x]) => Instruction::LdIVx { x: vx(x)? },
+        ("LD", ["DT", x]) => Instruction::LdDtVx { x: vx(x)? },
+        ("LD", ["ST", x]) => Instruction::LdStVx { x: vx(x)? },
+        ("LD", ["F", x]) => Instruction::LdFVx { x: vx(x)? },
+        ("LD", ["B", x]) => Instruction::LdBVx { x: vx(x)? },
+        ("LD", [x, "[I]"]) => Instruction::LdVxI { x: vx(x)? },
+        ("LD", [x, "DT"]) => Instruction::LdVxDt { x: vx(x)? },
+        ("LD", [x, "K"]) => Instruction::LdVxK { x: vx(x)? },
+        ("LD", [x, y]) => match register(y) {
+            Some(y) => Instruction::LdVxVy { x: vx(x)?, y },
+            None => Instruction::LdVxByte {
+                x: vx(x)?,
+                byte: byte(y)?,
+            },
+        },
+        ("RND", [x, b]) => Instruction::RndVxByte {
+            x: vx(x)?,
+            byte: byte(b)?,
+        },
+        ("DRW", [x, y, n]) => Instruction::DrwVxVyNibble {
+            x: vx(x)?,
+            y: vx(y)?,
+            nibble: number(n, 0xF)?,
+        },
+        ("SKP", [x]) => Instruction::SkpVx { x: vx(x)? },
+        ("SKNP", [x]) => Instruction::SknpVx { x: vx(x)? },
+        (
+            "CLS" | "RET" | "SYS" | "JP" | "CALL" | "SE" | "SNE" | "ADD" | "OR" | "AND" | "XOR"
+            | "SUB" | "SHR" | "SUBN" | "SHL" | "LD" | "RND" | "DRW" | "SKP" | "SKNP",
+            _,
+        ) => return Err("wrong operands"),
+        _ => return Err("unknown mnemonic"),
+    };
+    Ok(instruction)
+}
+
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s).map_err(|reason| ParseError {
+            text: s.trim().to_string(),
+            reason,
+        })
+    }
+}
+
+// Assembles one line of disassembler output into its opcode, `DW NNNN` gives
+// the raw word and anything after a `;` is a comment
+pub fn assemble_line(line: &str) -> Result<u16, ParseError> {
+    let code = line.split(';').next().unwrap_or("").trim();
+    let upper = code.to_ascii_uppercase();
+    match upper.strip_prefix("DW") {
+        Some(word) if word.starts_with(char::is_whitespace) => {
+            number(word.trim(), 0xFFFF).map_err(|reason| ParseError {
+                text: code.to_string(),
+                reason,
+            })
+        }
+        _ => code.parse::<Instruction>().map(|i| i.encode()),
+    }
+}
@@ -0,0 +1,348 @@
+use crate::chip8::cpu::Cpu;
+use core::fmt;
+
+// Where the VIP interpreter keeps V0-VF and the display buffer in a 4K machine
+pub const VARIABLES: usize = 0xEF0;
+pub const DISPLAY: usize = 0xF00;
+// The VIP interpreter stack grows down from here, shared with the routines
+pub const STACK: u16 = 0xECF;
+// Instructions a routine may execute before it is considered stuck
+pub const MAX_STEPS: u32 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    // IDL waits for an interrupt or a DMA request that never comes
+    Idle,
+    // 68, the 1804 extended opcodes
+    Extended,
+    // no SEP 4 back to the interpreter after MAX_STEPS instructions
+    Timeout,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::Idle => write!(f, "IDL with nothing to wake the cpu"),
+            Fault::Extended => write!(f, "1804 extended opcode"),
+            Fault::Timeout => write!(f, "no return after {} instructions", MAX_STEPS),
+        }
+    }
+}
+
+// An RCA CDP1802 : sixteen 16 bit registers, P selects the program counter
+// and X the data pointer
+pub struct Cdp1802 {
+    pub r: [u16; 16],
+    pub p: u8,
+    pub x: u8,
+    pub d: u8,
+    pub df: bool,
+    pub q: bool,
+    pub ie: bool,
+    pub t: u8,
+}
+
+impl Cdp1802 {
+    // The state after a reset : everything at 0, interrupts enabled
+    pub fn new() -> Self {
+        Self {
+            r: [0; 16],
+            p: 0,
+            x: 0,
+            d: 0,
+            df: false,
+            q: false,
+            ie: true,
+            t: 0,
+        }
+    }
+
+    // The 64K address space mirrors the memory
+    fn read(memory: &[u8], addr: u16) -> u8 {
+        memory[addr as usize % memory.len()]
+    }
+
+    fn write(memory: &mut [u8], addr: u16, value: u8) {
+        let len = memory.len();
+        memory[addr as usize % len] = value;
+    }
+
+    // The byte at R(P), moving R(P) past it
+    fn immediate(&mut self, memory: &[u8]) -> u8 {
+        let value = Self::read(memory, self.r[self.p as usize]);
+        self.r[self.p as usize] = self.r[self.p as usize].wrapping_add(1);
+        value
+    }
+
+    fn rx(&self) -> u16 {
+        self.r[self.x as usize]
+    }
+
+    fn add(&mut self, a: u8, b: u8, carry: bool) {
+        let sum = a as u16 + b as u16 + carry as u16;
+        self.d = sum as u8;
+        self.df = sum > 0xFF;
+    }
+
+    // a - b, DF is set when there is no borrow
+    fn sub(&mut self, a: u8, b: u8, borrow: bool) {
+        let diff = a as i16 - b as i16 - borrow as i16;
+        self.d = diff as u8;
+        self.df = diff >= 0;
+    }
+
+    fn short_branch(&mut self, memory: &[u8], taken: bool) {
+        let p = self.p as usize;
+        if taken {
+            let low = Self::read(memory, self.r[p]);
+            self.r[p] = (self.r[p] & 0xFF00) | low as u16;
+        } else {
+            self.r[p] = self.r[p].wrapping_add(1);
+        }
+    }
+
+    fn long_branch(&mut self, memory: &[u8], taken: bool) {
+        let p = self.p as usize;
+        if taken {
+            let high = Self::read(memory, self.r[p]);
+            let low = Self::read(memory, self.r[p].wrapping_add(1));
+            self.r[p] = (high as u16) << 8 | low as u16;
+        } else {
+            self.r[p] = self.r[p].wrapping_add(2);
+        }
+    }
+
+    fn long_skip(&mut self, skip: bool) {
+        if skip {
+            let p = self.p as usize;
+            self.r[p] = self.r[p].wrapping_add(2);
+        }
+    }
+
+    // Executes one instruction, the EF inputs read low and the bus reads 0
+    pub fn step(&mut self, memory: &mut [u8]) -> Result<(), Fault> {
+        let op = self.immediate(memory);
+        let n = (op & 0x0F) as usize;
+
+        match op >> 4 {
+            0x0 if n == 0 => {
+                // stays on the IDL
+                self.r[self.p as usize] = self.r[self.p as usize].wrapping_sub(1);
+                return Err(Fault::Idle);
+            }
+            0x0 => self.d = Self::read(memory, self.r[n]),
+            0x1 => self.r[n] = self.r[n].wrapping_add(1),
+            0x2 => self.r[n] = self.r[n].wrapping_sub(1),
+            0x3 => {
+                let d_zero = self.d == 0;
+                let taken = match n {
+                    0x0 => true,
+                    0x1 => self.q,
+                    0x2 => d_zero,
+                    0x3 => self.df,
+                    // B1-B4, the EF lines are never asserted
+                    0x4..=0x7 => false,
+                    // SKP skips the byte a branch would read
+                    0x8 => {
+                        self.r[self.p as usize] = self.r[self.p as usize].wrapping_add(1);
+                        return Ok(());
+                    }
+                    0x9 => !self.q,
+                    0xA => !d_zero,
+                    0xB => !self.df,
+                    _ => true,
+                };
+                self.short_branch(memory, taken);
+            }
+            0x4 => {
+                self.d = Self::read(memory, self.r[n]);
+                self.r[n] = self.r[n].wrapping_add(1);
+            }
+            0x5 => Self::write(memory, self.r[n], self.d),
+            0x6 => match n {
+                0x0 => self.r[self.x as usize] = self.rx().wrapping_add(1),
+                // OUT puts M(R(X)) on the bus, nothing listens
+                0x1..=0x7 => self.r[self.x as usize] = self.rx().wrapping_add(1),
+                0x8 => return Err(Fault::Extended),
+                // INP reads 0 from the bus
+                _ => {
+                    self.d = 0;
+                    Self::write(memory, self.rx(), 0);
+                }
+            },
+            0x7 => match n {
+                0x0 | 0x1 => {
+                    let xp = Self::read(memory, self.rx());
+                    self.r[self.x as usize] = self.rx().wrapping_add(1);
+                    self.x = xp >> 4;
+                    self.p = xp & 0x0F;
+                    self.ie = n == 0;
+                }
+                0x2 => {
+                    self.d = Self::read(memory, self.rx());
+                    self.r[self.x as usize] = self.rx().wrapping_add(1);
+                }
+                0x3 => {
+                    Self::write(memory, self.rx(), self.d);
+                    self.r[self.x as usize] = self.rx().wrapping_sub(1);
+                }
+                0x4 => self.add(Self::read(memory, self.rx()), self.d, self.df),
+                0x5 => self.sub(Self::read(memory, self.rx()), self.d, !self.df),
+                0x6 => {
+                    let carry = self.df;
+                    self.df = self.d & 1 != 0;
+                    self.d = self.d >> 1 | (carry as u8) << 7;
+                }
+                0x7 => self.sub(self.d, Self::read(memory, self.rx()), !self.df),
+                0x8 => Self::write(memory, self.rx(), self.t),
+                0x9 => {
+                    self.t = self.x << 4 | self.p;
+                    Self::write(memory, self.r[2], self.t);
+                    self.x = self.p;
+                    self.r[2] = self.r[2].wrapping_sub(1);
+                }
+                0xA => self.q = false,
+                0xB => self.q = true,
+                0xC => {
+                    let m = self.immediate(memory);
+                    self.add(m, self.d, self.df);
+                }
+                0xD => {
+                    let m = self.immediate(memory);
+                    self.sub(m, self.d, !self.df);
+                }
+                0xE => {
+                    let carry = self.df;
+                    self.df = self.d & 0x80 != 0;
+                    self.d = self.d << 1 | carry as u8;
+                }
+                _ => {
+                    let m = self.immediate(memory);
+                    self.sub(self.d, m, !self.df);
+                }
+            },
+            0x8 => self.d = self.r[n] as u8,
+            0x9 => self.d = (self.r[n] >> 8) as u8,
+            0xA => self.r[n] = (self.r[n] & 0xFF00) | self.d as u16,
+            0xB => self.r[n] = (self.r[n] & 0x00FF) | (self.d as u16) << 8,
+            0xC => {
+                let d_zero = self.d == 0;
+                match n {
+                    0x0 => self.long_branch(memory, true),
+                    0x1 => self.long_branch(memory, self.q),
+                    0x2 => self.long_branch(memory, d_zero),
+                    0x3 => self.long_branch(memory, self.df),
+                    0x4 => {}
+                    0x5 => self.long_skip(!self.q),
+                    0x6 => self.long_skip(!d_zero),
+                    0x7 => self.long_skip(!self.df),
+                    0x8 => self.long_skip(true),
+                    0x9 => self.long_branch(memory, !self.q),
+                    0xA => self.long_branch(memory, !d_zero),
+                    0xB => self.long_branch(memory, !self.df),
+                    0xC => self.long_skip(self.ie),
+                    0xD => self.long_skip(self.q),
+                    0xE => self.long_skip(d_zero),
+                    _ => self.long_skip(self.df),
+                }
+            }
+            0xD => self.p = n as u8,
+            0xE => self.x = n as u8,
+            _ => {
+                // F8-FF take their operand from R(P) instead of R(X), but FE has none
+                let m = if n >= 8 && n != 0xE {
+                    self.immediate(memory)
+                } else {
+                    Self::read(memory, self.rx())
+                };
+                match n & 0x7 {
+                    0x0 => self.d = m,
+                    0x1 => self.d |= m,
+                    0x2 => self.d &= m,
+                    0x3 => self.d ^= m,
+                    0x4 => self.add(m, self.d, false),
+                    0x5 => self.sub(m, self.d, false),
+                    0x6 if n == 0x6 => {
+                        self.df = self.d & 1 != 0;
+                        self.d >>= 1;
+                    }
+                    0x6 => {
+                        self.df = self.d & 0x80 != 0;
+                        self.d <<= 1;
+                    }
+                    _ => self.sub(self.d, m, false),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Cdp1802 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Copies the screen into the VIP display buffer, one bit per pixel
+fn store_display(cpu: &mut Cpu) {
+    for y in 0..32 {
+        for byte in 0..8 {
+            let mut bits = 0;
+            for bit in 0..8 {
+                if cpu.display[(y, byte * 8 + bit)] {
+                    bits |= 0x80 >> bit;
+                }
+            }
+            cpu.memory[DISPLAY + y * 8 + byte] = bits;
+        }
+    }
+}
+
+fn load_display(cpu: &mut Cpu) {
+    for y in 0..32 {
+        for x in 0..64 {
+            cpu.display[(y, x)] = cpu.memory[DISPLAY + y * 8 + x / 8] & (0x80 >> (x % 8)) != 0;
+        }
+    }
+}
+
+// Runs the machine code routine at `addr` the way the VIP interpreter calls
+// it : P = 3, X = 2 on the stack, R4 the interpreter loop the routine returns
+// to with SEP 4, R5 the CHIP-8 PC, RA = I and RB the display page. V0-VF
+// and the screen are in memory while it runs
+pub fn call(cpu: &mut Cpu, addr: u16) -> Result<(), Fault> {
+    cpu.memory[VARIABLES..VARIABLES + 16].copy_from_slice(&cpu.registers);
+    store_display(cpu);
+
+    let mut core = Cdp1802::new();
+    core.p = 3;
+    core.x = 2;
+    core.r[2] = STACK;
+    core.r[3] = addr;
+    core.r[5] = cpu.program_counter;
+    core.r[6] = VARIABLES as u16;
+    core.r[7] = VARIABLES as u16;
+    core.r[0xA] = cpu.index;
+    core.r[0xB] = DISPLAY as u16;
+
+    let mut result = Err(Fault::Timeout);
+    for _ in 0..MAX_STEPS {
+        if let Err(fault) = core.step(&mut cpu.memory) {
+            result = Err(fault);
+            break;
+        }
+        if core.p == 4 {
+            result = Ok(());
+            break;
+        }
+    }
+
+    // the routine may have changed any of them, even when it failed
+    cpu.registers
+        .copy_from_slice(&cpu.memory[VARIABLES..VARIABLES + 16]);
+    load_display(cpu);
+    cpu.index = core.r[0xA] & 0x0FFF;
+    cpu.program_counter = core.r[5] & 0x0FFF;
+    result
+}
@@ -0,0 +1,477 @@
+use crate::chip8::decoder::decode;
+use crate::chip8::disassembler::Disassembler;
+use crate::chip8::instructions::Chip8;
+use crate::chip8::platform::Platform;
+use crate::chip8::program::Program;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fmt::Write;
+
+pub const ENTRY: u16 = 0x200;
+
+// How control leaves a single instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    Skip,
+    Jump(u16),
+    Call(u16),
+    Return,
+    Indirect(u16),
+    Halt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    Fallthrough,
+    Skip,
+    Jump,
+    Call,
+}
+
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EdgeKind::Fallthrough => "fallthrough",
+            EdgeKind::Skip => "skip",
+            EdgeKind::Jump => "jump",
+            EdgeKind::Call => "call",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    pub target: u16,
+    pub kind: EdgeKind,
+}
+
+pub struct BasicBlock {
+    pub start: u16,
+    // (address, opcode) pairs
+    pub instructions: Vec<(u16, u16)>,
+    pub successors: Vec<Edge>,
+    pub terminator: Flow,
+}
+
+impl BasicBlock {
+    pub fn end(&self) -> u16 {
+        self.start + 2 * self.instructions.len() as u16
+    }
+
+    pub fn is_indirect(&self) -> bool {
+        matches!(self.terminator, Flow::Indirect(_))
+    }
+}
+
+pub struct Cfg {
+    pub entry: u16,
+    pub blocks: BTreeMap<u16, BasicBlock>,
+    // subroutine entry -> start addresses of the blocks reachable without following calls
+    pub subroutines: BTreeMap<u16, Vec<u16>>,
+}
+
+struct FlowDecoder {
+    flow: Flow,
+}
+
+pub fn flow(op: u16) -> Flow {
+    let mut decoder = FlowDecoder { flow: Flow::Next };
+    decode(op, &mut decoder);
+    decoder.flow
+}
+
+impl Cfg {
+    pub fn build(p: &Program) -> Self {
+        let fetch = |addr: u16| -> Option<u16> {
+            let offset = addr.checked_sub(ENTRY)? as usize;
+            if offset + 1 < p.content.len() {
+                Some(((p.content[offset] as u16) << 8) | p.content[offset + 1] as u16)
+            } else {
+                None
+            }
+        };
+
+        // first pass : find every reachable instruction and the block leaders
+        let mut visited = BTreeSet::new();
+        let mut leaders = BTreeSet::from([ENTRY]);
+        let mut calls = BTreeSet::new();
+        let mut worklist = vec![ENTRY];
+
+        while let Some(mut addr) = worklist.pop() {
+            while !visited.contains(&addr) {
+                let op = match fetch(addr) {
+                    Some(op) => op,
+                    None => break,
+                };
+                visited.insert(addr);
+
+                match flow(op) {
+                    Flow::Next => {
+                        addr = addr.wrapping_add(2);
+                        continue;
+                    }
+                    Flow::Skip => {
+                        let (next, skipped) = (addr.wrapping_add(2), addr.wrapping_add(4));
+                        leaders.extend([next, skipped]);
+                        worklist.extend([next, skipped]);
+                    }
+                    Flow::Jump(target) => {
+                        leaders.insert(target);
+                        worklist.push(target);
+                    }
+                    Flow::Call(target) => {
+                        calls.insert(target);
+                        leaders.extend([target, addr.wrapping_add(2)]);
+                        worklist.extend([target, addr.wrapping_add(2)]);
+                    }
+                    Flow::Return | Flow::Indirect(_) | Flow::Halt => {}
+                }
+                break;
+            }
+        }
+
+        // second pass : cut the reachable instructions into blocks
+        let mut blocks = BTreeMap::new();
+        for &leader in leaders.iter().filter(|l| visited.contains(l)) {
+            let mut instructions = Vec::new();
+            let mut addr = leader;
+            let terminator = loop {
+                let op = fetch(addr).unwrap();
+                instructions.push((addr, op));
+                let f = flow(op);
+                let next = addr.wrapping_add(2);
+                if f != Flow::Next || !visited.contains(&next) || leaders.contains(&next) {
+                    break f;
+                }
+                addr = next;
+            };
+
+            let next = addr.wrapping_add(2);
+            let successors = match terminator {
+                Flow::Next if visited.contains(&next) => vec![Edge {
+                    target: next,
+                    kind: EdgeKind::Fallthrough,
+                }],
+                Flow::Skip => vec![
+                    Edge {
+                        target: next,
+                        kind: EdgeKind::Fallthrough,
+                    },
+                    Edge {
+                        target: next.wrapping_add(2),
+                        kind: EdgeKind::Skip,
+                    },
+                ],
+                Flow::Jump(target) => vec![Edge {
+                    target,
+                    kind: EdgeKind::Jump,
+                }],
+                Flow::Call(target) => vec![
+                    Edge {
+                        target,
+                        kind: EdgeKind::Call,
+                    },
+                    Edge {
+                        target: next,
+                        kind: EdgeKind::Fallthrough,
+                    },
+                ],
+                _ => Vec::new(),
+            };
+            // drop edges leading outside of the program
+            let successors = successors
+                .into_iter()
+                .filter(|e| visited.contains(&e.target))
+                .collect();
+
+            blocks.insert(
+                leader,
+                BasicBlock {
+                    start: leader,
+                    instructions,
+                    successors,
+                    terminator,
+                },
+            );
+        }
+
+        // group the blocks by subroutine, calls are not followed
+        let mut subroutines = BTreeMap::new();
+        for &entry in [ENTRY].iter().chain(calls.iter()) {
+            if !blocks.contains_key(&entry) {
+                continue;
+            }
+            let mut members = BTreeSet::new();
+            let mut stack = vec![entry];
+            while let Some(start) = stack.pop() {
+                if !members.insert(start) {
+                    continue;
+                }
+                let block: &BasicBlock = &blocks[&start];
+                for edge in &block.successors {
+                    if edge.kind != EdgeKind::Call {
+                        stack.push(edge.target);
+                    }
+                }
+            }
+            subroutines.insert(entry, members.into_iter().collect());
+        }
+
+        Self {
+            entry: ENTRY,
+            blocks,
+            subroutines,
+        }
+    }
+
+    fn selected(&self, subroutine: Option<u16>) -> Vec<u16> {
+        match subroutine {
+            Some(entry) => self.subroutines.get(&entry).cloned().unwrap_or_default(),
+            None => self.blocks.keys().copied().collect(),
+        }
+    }
+
+    pub fn to_dot(&self, subroutine: Option<u16>) -> String {
+        let selected = self.selected(subroutine);
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph cfg {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+
+        match subroutine {
+            Some(_) => {
+                for start in &selected {
+                    write_dot_node(&mut dot, &self.blocks[start], "    ");
+                }
+            }
+            None => {
+                // a block shared by several subroutines is drawn in the first one only
+                let mut placed = BTreeSet::new();
+                for (entry, members) in &self.subroutines {
+                    writeln!(dot, "    subgraph cluster_{:03X} {{", entry).unwrap();
+                    writeln!(dot, "        label=\"sub_{:03X}\";", entry).unwrap();
+                    for start in members {
+                        if placed.insert(*start) {
+                            write_dot_node(&mut dot, &self.blocks[start], "        ");
+                        }
+                    }
+                    writeln!(dot, "    }}").unwrap();
+                }
+            }
+        }
+
+        let mut stubs = BTreeSet::new();
+        for start in &selected {
+            for edge in &self.blocks[start].successors {
+                let style = match edge.kind {
+                    EdgeKind::Fallthrough => "",
+                    EdgeKind::Skip => ", style=bold, color=darkgreen",
+                    EdgeKind::Jump => ", color=blue",
+                    EdgeKind::Call => ", style=dashed",
+                };
+                let target = if subroutine.is_some() && edge.kind == EdgeKind::Call {
+                    stubs.insert(edge.target);
+                    format!("sub_{:03X}", edge.target)
+                } else {
+                    format!("b_{:03X}", edge.target)
+                };
+                writeln!(
+                    dot,
+                    "    b_{:03X} -> {} [label=\"{}\"{}];",
+                    start, target, edge.kind, style
+                )
+                .unwrap();
+            }
+        }
+        for stub in stubs {
+            writeln!(dot, "    sub_{:03X} [shape=ellipse];", stub).unwrap();
+        }
+
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    pub fn to_json(&self, subroutine: Option<u16>) -> String {
+        let subroutines = self
+            .subroutines
+            .iter()
+            .filter(|(entry, _)| subroutine.is_none_or(|s| s == **entry))
+            .map(|(entry, blocks)| JsonSubroutine {
+                entry: *entry,
+                blocks,
+            })
+            .collect();
+        let blocks = self
+            .selected(subroutine)
+            .iter()
+            .map(|start| {
+                let block = &self.blocks[start];
+                JsonBlock {
+                    start: block.start,
+                    end: block.end(),
+                    indirect: block.is_indirect(),
+                    instructions: block
+                        .instructions
+                        .iter()
+                        .map(|(addr, op)| JsonInstruction {
+                            addr: *addr,
+                            op: *op,
+                            text: mnemonic(*op),
+                        })
+                        .collect(),
+                    successors: &block.successors,
+                }
+            })
+            .collect();
+        let json = JsonCfg {
+            entry: self.entry,
+            subroutines,
+            blocks,
+        };
+        serde_json::to_string_pretty(&json).unwrap()
+    }
+}
+
+// The layout of the JSON export
+#[derive(Serialize)]
+struct JsonCfg<'a> {
+    entry: u16,
+    subroutines: Vec<JsonSubroutine<'a>>,
+    blocks: Vec<JsonBlock<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonSubroutine<'a> {
+    entry: u16,
+    blocks: &'a [u16],
+}
+
+#[derive(Serialize)]
+struct JsonBlock<'a> {
+    start: u16,
+    end: u16,
+    indirect: bool,
+    instructions: Vec<JsonInstruction>,
+    successors: &'a [Edge],
+}
+
+#[derive(Serialize)]
+struct JsonInstruction {
+    addr: u16,
+    op: u16,
+    text: String,
+}
+
+fn mnemonic(op: u16) -> String {
+    let mut disassembler = Disassembler::new();
+    disassembler.step(op);
+    disassembler.to_string().trim_end().to_string()
+}
+
+fn write_dot_node(dot: &mut String, block: &BasicBlock, indent: &str) {
+    let mut label = String::new();
+    for (addr, op) in &block.instructions {
+        write!(label, "{:03X}: {}\\l", addr, escape_dot(&mnemonic(*op))).unwrap();
+    }
+    let extra = if block.is_indirect() {
+        ", color=red, xlabel=\"indirect\""
+    } else {
+        ""
+    };
+    writeln!(
+        dot,
+        "{}b_{:03X} [label=\"{}\"{}];",
+        indent, block.start, label, extra
+    )
+    .unwrap();
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Chip8 for FlowDecoder {
+    // 0
+    fn cls(&mut self) {}
+    fn ret(&mut self) {
+        self.flow = Flow::Return;
+    }
+    fn sys_addr(&mut self, _addr: u16) {}
+    // 1
+    fn jp_addr(&mut self, addr: u16) {
+        self.flow = Flow::Jump(addr);
+    }
+    // 2
+    fn call_addr(&mut self, addr: u16) {
+        self.flow = Flow::Call(addr);
+    }
+    // 3
+    fn se_vx_byte(&mut self, _x: u8, _byte: u8) {
+        self.flow = Flow::Skip;
+    }
+    // 4
+    fn sne_vx_byte(&mut self, _x: u8, _byte: u8) {
+        self.flow = Flow::Skip;
+    }
+    // 5
+    fn se_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.flow = Flow::Skip;
+    }
+    // 6
+    fn ld_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // 7
+    fn add_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // 8
+    fn ld_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn or_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn and_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn xor_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn add_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn sub_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn shr_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn subn_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn shl_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn sne_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.flow = Flow::Skip;
+    }
+    // A
+    fn ld_i_addr(&mut self, _addr: u16) {}
+    // B
+    fn jp_v0_addr(&mut self, addr: u16) {
+        self.flow = Flow::Indirect(addr);
+    }
+    // C
+    fn rnd_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // D
+    fn drw_vx_vy_nibble(&mut self, _x: u8, _y: u8, _nibble: u16) {}
+    // E
+    fn skp_vx(&mut self, _x: u8) {
+        self.flow = Flow::Skip;
+    }
+    fn sknp_vx(&mut self, _x: u8) {
+        self.flow = Flow::Skip;
+    }
+    // F
+    fn ld_vx_dt(&mut self, _x: u8) {}
+    fn ld_vx_k(&mut self, _x: u8) {}
+    fn ld_dt_vx(&mut self, _x: u8) {}
+    fn ld_st_vx(&mut self, _x: u8) {}
+    fn add_i_vx(&mut self, _x: u8) {}
+    fn ld_f_vx(&mut self, _x: u8) {}
+    fn ld_b_vx(&mut self, _x: u8) {}
+    fn ld_i_vx(&mut self, _x: u8) {}
+    fn ld_vx_i(&mut self, _x: u8) {}
+
+    fn unknown(&mut self, op: u16) {
+        // extension opcodes keep going, except EXIT and the 4 bytes long F000 NNNN
+        self.flow = match Platform::of_opcode(op) {
+            Some(_) if op == 0x00FD || op == 0xF000 => Flow::Halt,
+            Some(_) => Flow::Next,
+            None => Flow::Halt,
+        };
+    }
+}
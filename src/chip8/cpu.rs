@@ -1,6 +1,6 @@
 use crate::screen::Screen;
 use crate::chip8::font::FONT;
-use crate::chip8::program::Program;
+use crate::chip8::program::{LoadError, Program};
 
 pub struct Cpu {
     pub memory: [u8; 4096],
@@ -38,8 +38,28 @@ impl Cpu {
         cpu
     }
 
-    pub fn load_program(&mut self, p: &Program) {
-        self.memory[0x200..(0x200 + p.content.len())].copy_from_slice(&p.content);
+    // Copies the program at its address and starts there, memory is left
+    // untouched when it does not fit
+    pub fn load_program(&mut self, p: &Program) -> Result<(), LoadError> {
+        let start = p.address as usize;
+        let end = start + p.content.len();
+        if start >= self.memory.len() {
+            return Err(LoadError::BadAddress(p.address));
+        }
+        if end > self.memory.len() {
+            return Err(LoadError::TooLarge {
+                size: p.content.len(),
+                max: self.memory.len() - start,
+            });
+        }
+        self.memory[start..end].copy_from_slice(&p.content);
+        self.program_counter = p.address;
+        Ok(())
+    }
+}
 
+impl Default for Cpu {
+    fn default() -> Self {
+        Self::new()
     }
 }
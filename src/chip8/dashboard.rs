@@ -0,0 +1,149 @@
+use crate::chip8::debugger::Debugger;
+use crate::chip8::machine::{AudioSink, Clock};
+use crate::chip8::terminal::{quits, Glyphs, Terminal};
+use crossterm::cursor::MoveTo;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::queue;
+use crossterm::style::{Attribute, Print, SetAttribute};
+use std::io::{self, Write};
+use std::time::Duration;
+
+const CODE_ROWS: usize = 14;
+const CODE_WIDTH: usize = 40;
+const SIDE_WIDTH: usize = 34;
+const SIDE_ROWS: usize = 16;
+const HELP: &str =
+    "F5/p continue  F10/n step  F9/b breakpoint  F4/g run to cursor  arrows, PgUp, PgDn, Home move  Esc quit";
+
+// The debugger in the terminal : the screen with the registers and the call
+// stack on its right, the disassembly around the cursor and the memory at I
+// below, the game keys still go to the keypad
+pub struct Dashboard {
+    pub debugger: Debugger,
+    terminal: Terminal,
+}
+
+impl Dashboard {
+    pub fn new(debugger: Debugger, terminal: Terminal) -> Self {
+        Self { debugger, terminal }
+    }
+
+    pub fn run(&mut self) -> io::Result<()> {
+        self.terminal.clear()?;
+        loop {
+            while event::poll(Duration::ZERO)? {
+                match event::read()? {
+                    Event::Key(key) if quits(&key) => return Ok(()),
+                    Event::Key(key) => self.key(&key),
+                    Event::Resize(..) => self.terminal.clear()?,
+                    _ => {}
+                }
+            }
+            self.terminal
+                .update_keys(&mut self.debugger.interpreter.cpu.keys);
+            self.debugger.frame();
+            self.draw()?;
+
+            let cpu = &self.debugger.interpreter.cpu;
+            let beeping = self.debugger.running && cpu.sound_timer > 0;
+            self.terminal.set_tone(beeping);
+            self.terminal.wait_frame();
+        }
+    }
+
+    fn key(&mut self, key: &KeyEvent) {
+        if key.kind == KeyEventKind::Release {
+            self.terminal.press(key);
+            return;
+        }
+        let debugger = &mut self.debugger;
+        match key.code {
+            KeyCode::F(5) | KeyCode::Char('p') if debugger.running => debugger.pause(),
+            KeyCode::F(5) | KeyCode::Char('p') => debugger.resume(),
+            KeyCode::F(10) | KeyCode::Char('n') => debugger.step(),
+            KeyCode::F(9) | KeyCode::Char('b') => debugger.toggle_breakpoint(debugger.cursor),
+            KeyCode::F(4) | KeyCode::Char('g') => debugger.run_to_cursor(),
+            KeyCode::Up => debugger.scroll(-1),
+            KeyCode::Down => debugger.scroll(1),
+            KeyCode::PageUp => debugger.scroll(-(CODE_ROWS as i32)),
+            KeyCode::PageDown => debugger.scroll(CODE_ROWS as i32),
+            KeyCode::Home => debugger.cursor = debugger.interpreter.cpu.program_counter,
+            _ => self.terminal.press(key),
+        }
+    }
+
+    fn draw(&mut self) -> io::Result<()> {
+        let debugger = &self.debugger;
+        let glyphs = self.terminal.glyphs;
+        self.terminal
+            .draw(&debugger.interpreter.cpu.display, 0, 0)?;
+
+        let side = match glyphs {
+            Glyphs::HalfBlocks => 66,
+            Glyphs::Braille => 34,
+        };
+        let mut lines = vec![String::from("Registers")];
+        lines.extend(debugger.registers());
+        lines.push(String::from("Stack"));
+        lines.extend(debugger.call_stack());
+        // a deep call stack is cut, the innermost calls stay
+        lines.resize(SIDE_ROWS, String::new());
+
+        let out = self.terminal.out();
+        for (row, line) in lines.iter().enumerate() {
+            let line = format!("{:<1$}", line, SIDE_WIDTH);
+            queue!(out, MoveTo(side, row as u16), Print(line))?;
+        }
+
+        let top = lines.len() as u16 + 1;
+        queue!(
+            out,
+            MoveTo(0, top),
+            Print(format!("{:<1$}", "Disassembly", CODE_WIDTH))
+        )?;
+        let mut code = debugger.disassembly(CODE_ROWS);
+        code.resize(CODE_ROWS, (0xFFFF, String::new()));
+        for (row, (addr, line)) in code.iter().enumerate() {
+            let line = format!("{:<1$}", line, CODE_WIDTH);
+            queue!(out, MoveTo(0, top + 1 + row as u16))?;
+            if *addr == debugger.cursor {
+                queue!(
+                    out,
+                    SetAttribute(Attribute::Reverse),
+                    Print(line),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(out, Print(line))?;
+            }
+        }
+
+        let column = CODE_WIDTH as u16 + 2;
+        queue!(
+            out,
+            MoveTo(column, top),
+            Print(format!("{:<1$}", "Memory at I", SIDE_WIDTH))
+        )?;
+        let mut memory = debugger.memory(CODE_ROWS);
+        memory.resize(CODE_ROWS, String::new());
+        for (row, line) in memory.iter().enumerate() {
+            let line = format!("{:<1$}", line, SIDE_WIDTH);
+            queue!(out, MoveTo(column, top + 1 + row as u16), Print(line))?;
+        }
+
+        let status = if debugger.interpreter.waiting_for_key() {
+            format!("{}, waiting for a key", debugger.status)
+        } else {
+            debugger.status.clone()
+        };
+        let bottom = top + 1 + CODE_ROWS as u16 + 1;
+        queue!(
+            out,
+            MoveTo(0, bottom),
+            Print(format!("{:<1$}", status, HELP.len())),
+            MoveTo(0, bottom + 1),
+            Print(HELP)
+        )?;
+        out.flush()
+    }
+}
@@ -0,0 +1,285 @@
+use crate::chip8::interpreter::Interpreter;
+use crate::chip8::platform::{Platform, Quirks};
+use crate::chip8::program::Program;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+// The programs.json of the CHIP-8 community database
+// (https://github.com/chip-8/chip-8-database) built into the binary
+const EMBEDDED: &str = include_str!("../../data/programs.json");
+
+#[derive(Deserialize)]
+struct RawProgram {
+    title: String,
+    #[serde(default)]
+    authors: Vec<String>,
+    release: Option<String>,
+    // the roms of the program keyed by their SHA-1
+    roms: HashMap<String, RawRom>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawRom {
+    // the platforms it runs on, the preferred one first
+    #[serde(default)]
+    platforms: Vec<String>,
+    // the quirks that differ from the platform
+    #[serde(default)]
+    quirky_platforms: HashMap<String, RawQuirks>,
+    tickrate: Option<u32>,
+    start_address: Option<u16>,
+    // up, down, left, right, a, b... to the keypad key
+    #[serde(default)]
+    keys: BTreeMap<String, u8>,
+    colors: Option<RawColors>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawQuirks {
+    shift: Option<bool>,
+    memory_increment_by_x: Option<bool>,
+    memory_leave_i_unchanged: Option<bool>,
+    wrap: Option<bool>,
+    jump: Option<bool>,
+    vblank: Option<bool>,
+    logic: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct RawColors {
+    // the background first, then the colours of the planes
+    #[serde(default)]
+    pixels: Vec<String>,
+    buzzer: Option<String>,
+    silence: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Colors {
+    pub pixels: Vec<[u8; 3]>,
+    pub buzzer: Option<[u8; 3]>,
+    pub silence: Option<[u8; 3]>,
+}
+
+// What the database knows about a rom
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub title: String,
+    pub authors: Vec<String>,
+    pub release: Option<String>,
+    pub platform: Option<Platform>,
+    pub quirks: Option<Quirks>,
+    pub tickrate: Option<u32>,
+    pub start_address: Option<u16>,
+    pub keys: BTreeMap<String, u8>,
+    pub colors: Option<Colors>,
+}
+
+impl Entry {
+    // Sets the quirks and the tickrate the rom needs, the frontends apply the
+    // keys and the colours
+    pub fn apply(&self, interpreter: &mut Interpreter) {
+        if let Some(quirks) = self.quirks {
+            interpreter.quirks = quirks;
+        }
+        if let Some(tickrate) = self.tickrate {
+            interpreter.tickrate = tickrate;
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "title     {}", self.title)?;
+        if !self.authors.is_empty() {
+            writeln!(f, "authors   {}", self.authors.join(", "))?;
+        }
+        if let Some(release) = &self.release {
+            writeln!(f, "release   {}", release)?;
+        }
+        if let Some(platform) = self.platform {
+            writeln!(f, "platform  {}", platform)?;
+        }
+        if let Some(quirks) = self.quirks {
+            writeln!(f, "quirks    {}", quirks)?;
+        }
+        if let Some(tickrate) = self.tickrate {
+            writeln!(f, "tickrate  {}", tickrate)?;
+        }
+        if let Some(address) = self.start_address {
+            writeln!(f, "address   {:X}", address)?;
+        }
+        if !self.keys.is_empty() {
+            let keys: Vec<String> = self
+                .keys
+                .iter()
+                .map(|(name, key)| format!("{}={:X}", name, key))
+                .collect();
+            writeln!(f, "keys      {}", keys.join(" "))?;
+        }
+        if let Some(colors) = &self.colors {
+            let pixels: Vec<String> = colors.pixels.iter().map(|c| hex(*c)).collect();
+            writeln!(f, "colours   {}", pixels.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// #rrggbb, None when malformed
+fn rgb(text: &str) -> Option<[u8; 3]> {
+    let digits = text.strip_prefix('#')?;
+    let value = u32::from_str_radix(digits, 16).ok().filter(|_| digits.len() == 6)?;
+    Some([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+// The platforms of the database, the extensions of SCHIP and XO-CHIP we do not
+// emulate fall back on the closest one
+fn platform(id: &str) -> Option<(Platform, Quirks)> {
+    let (platform, mut quirks) = match id {
+        "originalChip8" | "hybridVIP" | "chip8x" => (Platform::Chip8, Platform::Chip8.quirks()),
+        "modernChip8" => {
+            let mut quirks = Platform::Chip8.quirks();
+            quirks.vf_reset = false;
+            quirks.display_wait = false;
+            (Platform::Chip8, quirks)
+        }
+        "chip48" | "superchip1" | "superchip" | "megachip8" => {
+            (Platform::SuperChip, Platform::SuperChip.quirks())
+        }
+        "xochip" => (Platform::XoChip, Platform::XoChip.quirks()),
+        _ => return None,
+    };
+    // CHIP-48 increments I by X
+    quirks.memory |= id == "chip48";
+    Some((platform, quirks))
+}
+
+impl RawQuirks {
+    fn apply(&self, quirks: &mut Quirks) {
+        let Quirks {
+            vf_reset,
+            memory,
+            display_wait,
+            clipping,
+            shifting,
+            jumping,
+        } = quirks;
+        if let Some(shift) = self.shift {
+            *shifting = shift;
+        }
+        // I += X is closer to I += X + 1 than to I unchanged
+        if self.memory_increment_by_x == Some(true) {
+            *memory = true;
+        }
+        if let Some(unchanged) = self.memory_leave_i_unchanged {
+            *memory = !unchanged;
+        }
+        if let Some(wrap) = self.wrap {
+            *clipping = !wrap;
+        }
+        if let Some(jump) = self.jump {
+            *jumping = jump;
+        }
+        if let Some(vblank) = self.vblank {
+            *display_wait = vblank;
+        }
+        if let Some(logic) = self.logic {
+            *vf_reset = logic;
+        }
+    }
+}
+
+pub struct Database {
+    programs: Vec<RawProgram>,
+    // the SHA-1 of every rom to its program
+    hashes: HashMap<String, usize>,
+}
+
+impl Database {
+    pub fn parse(json: &str) -> serde_json::Result<Self> {
+        let programs: Vec<RawProgram> = serde_json::from_str(json)?;
+        let mut hashes = HashMap::new();
+        for (i, program) in programs.iter().enumerate() {
+            for sha1 in program.roms.keys() {
+                hashes.insert(sha1.to_ascii_lowercase(), i);
+            }
+        }
+        Ok(Self { programs, hashes })
+    }
+
+    pub fn embedded() -> Self {
+        Self::parse(EMBEDDED).expect("data/programs.json is valid")
+    }
+
+    // A programs.json of the database, a newer one than the embedded for example
+    pub fn from_path(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn lookup(&self, program: &Program) -> Option<Entry> {
+        self.get(&program.sha1_hex())
+    }
+
+    pub fn get(&self, sha1: &str) -> Option<Entry> {
+        let sha1 = sha1.to_ascii_lowercase();
+        let program = &self.programs[*self.hashes.get(&sha1)?];
+        let rom = &program.roms.iter().find(|(h, _)| h.eq_ignore_ascii_case(&sha1))?.1;
+
+        let found = rom.platforms.iter().find_map(|id| Some((id, platform(id)?)));
+        let (platform, quirks) = match found {
+            Some((id, (platform, mut quirks))) => {
+                if let Some(quirky) = rom.quirky_platforms.get(id) {
+                    quirky.apply(&mut quirks);
+                }
+                (Some(platform), Some(quirks))
+            }
+            None => (None, None),
+        };
+        let colors = rom.colors.as_ref().map(|colors| Colors {
+            pixels: colors.pixels.iter().filter_map(|c| rgb(c)).collect(),
+            buzzer: colors.buzzer.as_deref().and_then(rgb),
+            silence: colors.silence.as_deref().and_then(rgb),
+        });
+
+        Some(Entry {
+            title: program.title.clone(),
+            authors: program.authors.clone(),
+            release: program.release.clone(),
+            platform,
+            quirks,
+            tickrate: rom.tickrate,
+            start_address: rom.start_address,
+            keys: rom.keys.clone(),
+            colors,
+        })
+    }
+
+    // The roms whose title contains `text`, ignoring case, as (SHA-1, title)
+    pub fn search(&self, text: &str) -> Vec<(&str, &str)> {
+        let text = text.to_lowercase();
+        let mut found: Vec<(&str, &str)> = self
+            .programs
+            .iter()
+            .filter(|p| p.title.to_lowercase().contains(&text))
+            .flat_map(|p| p.roms.keys().map(|h| (h.as_str(), p.title.as_str())))
+            .collect();
+        found.sort_by(|a, b| (a.1, a.0).cmp(&(b.1, b.0)));
+        found
+    }
+}
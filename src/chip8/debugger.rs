@@ -0,0 +1,208 @@
+use crate::chip8::disassembler::Disassembler;
+use crate::chip8::interpreter::Interpreter;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Controls the execution of an interpreter and describes its state, the
+// frontend of chip8::dashboard draws it
+pub struct Debugger {
+    pub interpreter: Interpreter,
+    pub breakpoints: BTreeSet<u16>,
+    // the address selected in the disassembly
+    pub cursor: u16,
+    pub running: bool,
+    // where run to cursor stops
+    target: Option<u16>,
+    // the instruction at PC runs even under a breakpoint when resuming
+    resuming: bool,
+    // why the execution stopped last
+    pub status: String,
+}
+
+impl Debugger {
+    // Starts paused on the first instruction
+    pub fn new(interpreter: Interpreter) -> Self {
+        let cursor = interpreter.cpu.program_counter;
+        Self {
+            interpreter,
+            breakpoints: BTreeSet::new(),
+            cursor,
+            running: false,
+            target: None,
+            resuming: false,
+            status: String::from("paused"),
+        }
+    }
+
+    pub fn toggle_breakpoint(&mut self, addr: u16) {
+        if !self.breakpoints.remove(&addr) {
+            self.breakpoints.insert(addr);
+        }
+    }
+
+    // Executes one instruction and pauses
+    pub fn step(&mut self) {
+        self.running = false;
+        self.target = None;
+        self.status = String::from("paused");
+        self.execute();
+    }
+
+    pub fn resume(&mut self) {
+        self.running = true;
+        self.target = None;
+        self.resuming = true;
+        self.status = String::from("running");
+    }
+
+    pub fn pause(&mut self) {
+        self.stop("paused");
+    }
+
+    pub fn run_to_cursor(&mut self) {
+        self.resume();
+        self.target = Some(self.cursor);
+    }
+
+    // Moves the cursor by `n` instructions
+    pub fn scroll(&mut self, n: i32) {
+        self.cursor = (self.cursor as i32 + 2 * n).clamp(0, 0xFFE) as u16;
+    }
+
+    fn stop(&mut self, status: &str) {
+        self.running = false;
+        self.target = None;
+        self.status = status.to_string();
+        self.cursor = self.interpreter.cpu.program_counter;
+    }
+
+    fn execute(&mut self) -> bool {
+        match self.interpreter.try_step() {
+            Ok(()) => {
+                self.cursor = self.interpreter.cpu.program_counter;
+                true
+            }
+            Err(e) => {
+                self.stop(&e.to_string());
+                false
+            }
+        }
+    }
+
+    // Runs a frame of `tickrate` instructions while running, stopping before
+    // a breakpoint or the run to cursor address. The timers only tick while
+    // running and the cycle accurate clock is not used
+    pub fn frame(&mut self) {
+        if !self.running {
+            return;
+        }
+        for i in 0..self.interpreter.tickrate {
+            let pc = self.interpreter.cpu.program_counter;
+            if !self.resuming {
+                if self.breakpoints.contains(&pc) {
+                    self.stop(&format!("breakpoint at {:03X}", pc));
+                    return;
+                }
+                if self.target == Some(pc) {
+                    self.stop("paused");
+                    return;
+                }
+            }
+            if self.interpreter.waits_display(i == 0) {
+                break;
+            }
+            self.resuming = false;
+            if !self.execute() {
+                return;
+            }
+        }
+        self.interpreter.tick_timers();
+    }
+
+    // The instructions around the cursor, (address, text)
+    pub fn disassembly(&self, rows: usize) -> Vec<(u16, String)> {
+        let memory = &self.interpreter.cpu.memory;
+        // keep the parity of the cursor, code can be at odd addresses
+        let first = self.cursor as usize - (self.cursor as usize / 2).min(rows / 2) * 2;
+        (first..memory.len() - 1)
+            .step_by(2)
+            .take(rows)
+            .map(|addr| {
+                let op = (memory[addr] as u16) << 8 | memory[addr + 1] as u16;
+                let mut disassembler = Disassembler::new();
+                disassembler.step(op);
+                let pc = if addr as u16 == self.interpreter.cpu.program_counter {
+                    '>'
+                } else {
+                    ' '
+                };
+                let breakpoint = if self.breakpoints.contains(&(addr as u16)) {
+                    '*'
+                } else {
+                    ' '
+                };
+                let text = format!(
+                    "{}{} {:03X}  {:04X}  {}",
+                    breakpoint,
+                    pc,
+                    addr,
+                    op,
+                    disassembler.to_string().trim_end()
+                );
+                (addr as u16, text)
+            })
+            .collect()
+    }
+
+    // V0-VF four per line, then I, SP, DT, ST and PC
+    pub fn registers(&self) -> Vec<String> {
+        let cpu = &self.interpreter.cpu;
+        let mut lines: Vec<String> = cpu
+            .registers
+            .chunks(4)
+            .enumerate()
+            .map(|(row, values)| {
+                let cells: Vec<String> = values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| format!("V{:X} {:02X}", row * 4 + i, v))
+                    .collect();
+                cells.join("  ")
+            })
+            .collect();
+        lines.push(format!(
+            "I {:03X}  SP {:X}  DT {:02X}  ST {:02X}",
+            cpu.index, cpu.stack_pointer, cpu.delay_timer, cpu.sound_timer
+        ));
+        lines.push(format!("PC {:03X}", cpu.program_counter));
+        lines
+    }
+
+    // The return addresses, innermost first
+    pub fn call_stack(&self) -> Vec<String> {
+        let cpu = &self.interpreter.cpu;
+        (1..=cpu.stack_pointer.min(cpu.stack.len() - 1))
+            .rev()
+            .map(|i| format!("{:2} {:03X}", i, cpu.stack[i]))
+            .collect()
+    }
+
+    // Eight bytes per line from the line holding I
+    pub fn memory(&self, rows: usize) -> Vec<String> {
+        let memory = &self.interpreter.cpu.memory;
+        let first = (self.interpreter.cpu.index as usize & 0xFFF) & !7;
+        (first..memory.len())
+            .step_by(8)
+            .take(rows)
+            .map(|addr| {
+                let bytes: Vec<String> = memory[addr..addr + 8]
+                    .iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect();
+                format!("{:03X}  {}", addr, bytes.join(" "))
+            })
+            .collect()
+    }
+}
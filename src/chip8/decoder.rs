@@ -1,11 +1,14 @@
 use crate::chip8::instructions::Chip8;
+use crate::chip8::platform::Platform;
 
 pub fn decode(op: u16, i: &mut dyn Chip8) {
     match op & 0xF000 {
         0x0000 => match op {
             0x00E0 => i.cls(),
             0x00EE => i.ret(),
-            _ => i.unknown(op),
+            // SCHIP and XO-CHIP took some of the machine code addresses
+            _ if Platform::of_opcode(op).is_some() => i.unknown(op),
+            _ => i.sys_addr(op & 0x0FFF),
         },
         0x1000 => i.jp_addr(op & 0x0FFF),
         0x2000 => i.call_addr(op & 0x0FFF),
@@ -22,6 +25,7 @@ pub fn decode(op: u16, i: &mut dyn Chip8) {
         0x5000 => {
             let x = ((op & 0x0F00) >> 8) as u8;
             let y = ((op & 0x00F0) >> 4) as u8;
+            // the VIP ignores the last nibble, XO-CHIP gives 5XY2 and 5XY3 a meaning
             i.se_vx_vy(x, y);
         }
         0x6000 => {
@@ -53,6 +57,7 @@ pub fn decode(op: u16, i: &mut dyn Chip8) {
         0x9000 => {
             let x = ((op & 0x0F00) >> 8) as u8;
             let y = ((op & 0x00F0) >> 4) as u8;
+            // the VIP ignores the last nibble
             i.sne_vx_vy(x, y);
         }
         0xA000 => i.ld_i_addr(op & 0x0FFF),
@@ -65,7 +70,7 @@ pub fn decode(op: u16, i: &mut dyn Chip8) {
         0xD000 => {
             let x = ((op & 0x0F00) >> 8) as u8;
             let y = ((op & 0x00F0) >> 4) as u8;
-            let nibble = (op & 0x000F) as u16;
+            let nibble = op & 0x000F;
             i.drw_vx_vy_nibble(x, y, nibble);
         }
         0xE000 => {
@@ -1,166 +1,189 @@
 use crate::chip8::instructions::Chip8;
 use crate::chip8::program::Program;
 use crate::chip8::decoder::decode;
-use std::fmt::Write;
-use std::fmt;
+use alloc::string::String;
+use core::fmt::Write;
+use core::fmt;
 
-pub struct Disassembler {
-    code: String,
+// Writes one line per instruction into any fmt::Write, a String by default
+pub struct Disassembler<W = String> {
+    out: W,
+    // the first error of the writer, nothing is written after it
+    result: fmt::Result,
 }
 
 impl Disassembler {
     pub fn new() -> Self {
-        Self {
-            code: String::new(),
-        }
+        Self::with_writer(String::new())
+    }
+}
+
+impl<W: Write> Disassembler<W> {
+    pub fn with_writer(out: W) -> Self {
+        Self { out, result: Ok(()) }
     }
 
     pub fn step(&mut self, op: u16) {
         decode(op, self);
     }
 
+    // One line per word, an odd last byte is followed by the 00 of the
+    // memory after the program as when the interpreter fetches it
     pub fn disassemble(&mut self, p: &Program) {
-        let iter = p.content.iter().zip(p.content.iter().skip(1));
+        for word in p.content.chunks(2) {
+            let low = word.get(1).copied().unwrap_or(0);
+            self.step(u16::from_be_bytes([word[0], low]));
+        }
+    }
 
-        for (byte0, byte1) in iter {
-            let mut op: u16 = *byte0 as u16;
-            op <<= 8;
-            op |= *byte1 as u16;
+    // The writer, or the first error it returned
+    pub fn finish(self) -> Result<W, fmt::Error> {
+        self.result.map(|_| self.out)
+    }
 
-            self.step(op);
+    fn line(&mut self, args: fmt::Arguments) {
+        if self.result.is_ok() {
+            self.result = self.out.write_fmt(args).and_then(|_| self.out.write_char('\n'));
         }
     }
 }
 
+impl Default for Disassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl fmt::Display for Disassembler {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.code)
+        write!(f, "{}", self.out)
     }
 }
 
-impl Chip8 for Disassembler {
+impl<W: Write> Chip8 for Disassembler<W> {
     // 0
     fn cls(&mut self) {
-        writeln!(self.code, "CLS").unwrap()
+        self.line(format_args!("CLS"))
     }
     fn ret(&mut self) {
-        writeln!(self.code, "RET").unwrap()
+        self.line(format_args!("RET"))
     }
     fn sys_addr(&mut self, addr: u16) {
-        writeln!(self.code, "SYS {:X}", addr).unwrap()
+        self.line(format_args!("SYS {:X}", addr))
     }
     // 1
     fn jp_addr(&mut self, addr: u16) {
-        writeln!(self.code, "JP {:X}", addr).unwrap()
+        self.line(format_args!("JP {:X}", addr))
     }
     // 2
     fn call_addr(&mut self, addr: u16) {
-        writeln!(self.code, "CALL {:X}", addr).unwrap()
+        self.line(format_args!("CALL {:X}", addr))
     }
     // 3
     fn se_vx_byte(&mut self, x: u8, byte: u8) {
-        writeln!(self.code, "SE V{:X}, {:X}", x, byte).unwrap()
+        self.line(format_args!("SE V{:X}, {:X}", x, byte))
     }
     // 4
     fn sne_vx_byte(&mut self, x: u8, byte: u8) {
-        writeln!(self.code, "SNE V{:X}, {:X}", x, byte).unwrap()
+        self.line(format_args!("SNE V{:X}, {:X}", x, byte))
     }
     // 5
     fn se_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "SE V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("SE V{:X}, V{:X}", x, y))
     }
     // 6
     fn add_vx_byte(&mut self, x: u8, byte: u8) {
-        writeln!(self.code, "ADD V{:X}, {:X}", x, byte).unwrap()
+        self.line(format_args!("ADD V{:X}, {:X}", x, byte))
     }
     // 7
     fn ld_vx_byte(&mut self, x: u8, byte: u8) {
-        writeln!(self.code, "LD V{:X}, {:X}", x, byte).unwrap()
+        self.line(format_args!("LD V{:X}, {:X}", x, byte))
     }
     // 8
     fn ld_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "LD V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("LD V{:X}, V{:X}", x, y))
     }
     fn or_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "OR V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("OR V{:X}, V{:X}", x, y))
     }
     fn and_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "AND V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("AND V{:X}, V{:X}", x, y))
     }
     fn xor_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "XOR V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("XOR V{:X}, V{:X}", x, y))
     }
     fn add_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "ADD V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("ADD V{:X}, V{:X}", x, y))
     }
     fn sub_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "SUB V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("SUB V{:X}, V{:X}", x, y))
     }
     fn shr_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "SHR V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("SHR V{:X}, V{:X}", x, y))
     }
     fn subn_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "SUBN V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("SUBN V{:X}, V{:X}", x, y))
     }
     fn shl_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "SHL V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("SHL V{:X}, V{:X}", x, y))
     }
     fn sne_vx_vy(&mut self, x: u8, y: u8) {
-        writeln!(self.code, "SNE V{:X}, V{:X}", x, y).unwrap()
+        self.line(format_args!("SNE V{:X}, V{:X}", x, y))
     }
     // A
     fn ld_i_addr(&mut self, addr: u16) {
-        writeln!(self.code, "LD I, {:X}", addr).unwrap()
+        self.line(format_args!("LD I, {:X}", addr))
     }
     // B
     fn jp_v0_addr(&mut self, addr: u16) {
-        writeln!(self.code, "LD v0, {:X}", addr).unwrap()
+        self.line(format_args!("JP V0, {:X}", addr))
     }
     // C
     fn rnd_vx_byte(&mut self, x: u8, byte: u8) {
-        writeln!(self.code, "RND V{:X}, {:X}", x, byte).unwrap()
+        self.line(format_args!("RND V{:X}, {:X}", x, byte))
     }
     // D
     fn drw_vx_vy_nibble(&mut self, x: u8, y: u8, nibble: u16) {
-        writeln!(self.code, "DRW V{:X}, V{:X}, {:X}", x, y, nibble).unwrap()
+        self.line(format_args!("DRW V{:X}, V{:X}, {:X}", x, y, nibble))
     }
     // E
     fn skp_vx(&mut self, x: u8) {
-        writeln!(self.code, "SKP V{:X}", x).unwrap()
+        self.line(format_args!("SKP V{:X}", x))
     }
     fn sknp_vx(&mut self, x: u8) {
-        writeln!(self.code, "SKNP V{:X}", x).unwrap()
+        self.line(format_args!("SKNP V{:X}", x))
     }
     // F
     fn ld_vx_dt(&mut self, x: u8) {
-        writeln!(self.code, "LD V{:X}, DT", x).unwrap()
+        self.line(format_args!("LD V{:X}, DT", x))
     }
     fn ld_vx_k(&mut self, x: u8) {
-        writeln!(self.code, "LD V{:X}, K", x).unwrap()
+        self.line(format_args!("LD V{:X}, K", x))
     }
     fn ld_dt_vx(&mut self, x: u8) {
-        writeln!(self.code, "LD DT, V{:X}", x).unwrap()
+        self.line(format_args!("LD DT, V{:X}", x))
     }
     fn ld_st_vx(&mut self, x: u8) {
-        writeln!(self.code, "ST V{:X}", x).unwrap()
+        self.line(format_args!("LD ST, V{:X}", x))
     }
     fn add_i_vx(&mut self, x: u8) {
-        writeln!(self.code, "ADD I, V{:X}", x).unwrap()
+        self.line(format_args!("ADD I, V{:X}", x))
     }
     fn ld_f_vx(&mut self, x: u8) {
-        writeln!(self.code, "LD F, V{:X}", x).unwrap()
+        self.line(format_args!("LD F, V{:X}", x))
     }
     fn ld_b_vx(&mut self, x: u8) {
-        writeln!(self.code, "LD B, V{:X}", x).unwrap()
+        self.line(format_args!("LD B, V{:X}", x))
     }
     fn ld_i_vx(&mut self, x: u8) {
-        writeln!(self.code, "LD I, V{:X}", x).unwrap()
+        self.line(format_args!("LD [I], V{:X}", x))
     }
     fn ld_vx_i(&mut self, x: u8) {
-        writeln!(self.code, "LD V{:X}, I", x).unwrap()
+        self.line(format_args!("LD V{:X}, [I]", x))
     }
 
     fn unknown(&mut self, op: u16) {
-        writeln!(self.code, "unknown instruction : {:X}", op).unwrap()
+        // kept as a data word so the output assembles back
+        self.line(format_args!("DW {:04X}", op))
     }
 }
@@ -15,4 +15,56 @@ pub const FONT: [u8; 5 * 16] = [
     0xE0, 0x90, 0x90, 0x90, 0xE0, // D
     0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
-];
\ No newline at end of file
+];
+// The letters the hexadecimal digits of FONT lack and some punctuation, drawn
+// the same way to write text like the launcher titles
+const TEXT: [(char, [u8; 5]); 36] = [
+    ('G', [0xF0, 0x80, 0xB0, 0x90, 0xF0]),
+    ('H', [0x90, 0x90, 0xF0, 0x90, 0x90]),
+    ('I', [0xE0, 0x40, 0x40, 0x40, 0xE0]),
+    ('J', [0x70, 0x20, 0x20, 0xA0, 0xE0]),
+    ('K', [0x90, 0xA0, 0xC0, 0xA0, 0x90]),
+    ('L', [0x80, 0x80, 0x80, 0x80, 0xF0]),
+    ('M', [0x90, 0xF0, 0xF0, 0x90, 0x90]),
+    ('N', [0x90, 0xD0, 0xB0, 0x90, 0x90]),
+    ('O', [0x60, 0x90, 0x90, 0x90, 0x60]),
+    ('P', [0xF0, 0x90, 0xF0, 0x80, 0x80]),
+    ('Q', [0x60, 0x90, 0x90, 0xB0, 0x70]),
+    ('R', [0xE0, 0x90, 0xE0, 0xA0, 0x90]),
+    ('S', [0x70, 0x80, 0x60, 0x10, 0xE0]),
+    ('T', [0xF0, 0x40, 0x40, 0x40, 0x40]),
+    ('U', [0x90, 0x90, 0x90, 0x90, 0xF0]),
+    ('V', [0x90, 0x90, 0x90, 0xA0, 0x40]),
+    ('W', [0x90, 0x90, 0xF0, 0xF0, 0x90]),
+    ('X', [0x90, 0x90, 0x60, 0x90, 0x90]),
+    ('Y', [0x90, 0x90, 0x60, 0x40, 0x40]),
+    ('Z', [0xF0, 0x10, 0x60, 0x80, 0xF0]),
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('-', [0x00, 0x00, 0xF0, 0x00, 0x00]),
+    ('+', [0x00, 0x40, 0xE0, 0x40, 0x00]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0xF0]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x40]),
+    (',', [0x00, 0x00, 0x00, 0x40, 0x80]),
+    (':', [0x00, 0x40, 0x00, 0x40, 0x00]),
+    ('!', [0x40, 0x40, 0x40, 0x00, 0x40]),
+    ('?', [0xE0, 0x10, 0x60, 0x00, 0x40]),
+    ('\'', [0x40, 0x40, 0x00, 0x00, 0x00]),
+    ('"', [0xA0, 0xA0, 0x00, 0x00, 0x00]),
+    ('(', [0x20, 0x40, 0x40, 0x40, 0x20]),
+    (')', [0x40, 0x20, 0x20, 0x20, 0x40]),
+    ('/', [0x10, 0x20, 0x20, 0x40, 0x80]),
+    ('#', [0xA0, 0xF0, 0xA0, 0xF0, 0xA0]),
+    ('&', [0x40, 0xA0, 0x40, 0xA0, 0x50]),
+];
+
+// The 4x5 sprite of a character, the high nibble of each row, ignoring case.
+// The characters without one become '?'
+pub fn glyph(c: char) -> [u8; 5] {
+    let c = c.to_ascii_uppercase();
+    if let Some(digit) = c.to_digit(16) {
+        let i = digit as usize * 5;
+        return FONT[i..i + 5].try_into().unwrap();
+    }
+    let found = TEXT.iter().find(|(t, _)| *t == c);
+    found.map_or_else(|| glyph('?'), |(_, sprite)| *sprite)
+}
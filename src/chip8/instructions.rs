@@ -1,3 +1,7 @@
+use crate::chip8::decoder::decode;
+use core::error::Error;
+use core::fmt;
+
 pub trait Chip8 {
     // 0
     fn cls(&mut self);
@@ -68,3 +72,362 @@ pub trait SuperChip8 {
     fn ld_r_vx();
     fn ld_vx_r();
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    // 0
+    Cls,
+    Ret,
+    SysAddr { addr: u16 },
+    // 1
+    JpAddr { addr: u16 },
+    // 2
+    CallAddr { addr: u16 },
+    // 3
+    SeVxByte { x: u8, byte: u8 },
+    // 4
+    SneVxByte { x: u8, byte: u8 },
+    // 5
+    SeVxVy { x: u8, y: u8 },
+    // 6
+    LdVxByte { x: u8, byte: u8 },
+    // 7
+    AddVxByte { x: u8, byte: u8 },
+    // 8
+    LdVxVy { x: u8, y: u8 },
+    OrVxVy { x: u8, y: u8 },
+    AndVxVy { x: u8, y: u8 },
+    XorVxVy { x: u8, y: u8 },
+    AddVxVy { x: u8, y: u8 },
+    SubVxVy { x: u8, y: u8 },
+    ShrVxVy { x: u8, y: u8 },
+    SubnVxVy { x: u8, y: u8 },
+    ShlVxVy { x: u8, y: u8 },
+    // 9
+    SneVxVy { x: u8, y: u8 },
+    // A
+    LdIAddr { addr: u16 },
+    // B
+    JpV0Addr { addr: u16 },
+    // C
+    RndVxByte { x: u8, byte: u8 },
+    // D
+    DrwVxVyNibble { x: u8, y: u8, nibble: u16 },
+    // E
+    SkpVx { x: u8 },
+    SknpVx { x: u8 },
+    // F
+    LdVxDt { x: u8 },
+    LdVxK { x: u8 },
+    LdDtVx { x: u8 },
+    LdStVx { x: u8 },
+    AddIVx { x: u8 },
+    LdFVx { x: u8 },
+    LdBVx { x: u8 },
+    LdIVx { x: u8 },
+    LdVxI { x: u8 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownOpcode(pub u16);
+
+impl fmt::Display for UnknownOpcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown instruction : {:04X}", self.0)
+    }
+}
+
+impl Error for UnknownOpcode {}
+
+impl Instruction {
+    pub fn decode(op: u16) -> Result<Instruction, UnknownOpcode> {
+        let mut builder = Builder {
+            instruction: Err(UnknownOpcode(op)),
+        };
+        decode(op, &mut builder);
+        builder.instruction
+    }
+
+    // Operands wider than their field are truncated to it, like the
+    // interpreter reads them
+    pub fn encode(&self) -> u16 {
+        let xy = |op: u16, x: u8, y: u8| op | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4;
+        let xb = |op: u16, x: u8, byte: u8| op | (x as u16 & 0xF) << 8 | byte as u16;
+        let x_only = |op: u16, x: u8| op | (x as u16 & 0xF) << 8;
+
+        match *self {
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::SysAddr { addr } => addr & 0xFFF,
+            Instruction::JpAddr { addr } => 0x1000 | addr & 0xFFF,
+            Instruction::CallAddr { addr } => 0x2000 | addr & 0xFFF,
+            Instruction::SeVxByte { x, byte } => xb(0x3000, x, byte),
+            Instruction::SneVxByte { x, byte } => xb(0x4000, x, byte),
+            Instruction::SeVxVy { x, y } => xy(0x5000, x, y),
+            Instruction::LdVxByte { x, byte } => xb(0x6000, x, byte),
+            Instruction::AddVxByte { x, byte } => xb(0x7000, x, byte),
+            Instruction::LdVxVy { x, y } => xy(0x8000, x, y),
+            Instruction::OrVxVy { x, y } => xy(0x8001, x, y),
+            Instruction::AndVxVy { x, y } => xy(0x8002, x, y),
+            Instruction::XorVxVy { x, y } => xy(0x8003, x, y),
+            Instruction::AddVxVy { x, y } => xy(0x8004, x, y),
+            Instruction::SubVxVy { x, y } => xy(0x8005, x, y),
+            Instruction::ShrVxVy { x, y } => xy(0x8006, x, y),
+            Instruction::SubnVxVy { x, y } => xy(0x8007, x, y),
+            Instruction::ShlVxVy { x, y } => xy(0x800E, x, y),
+            Instruction::SneVxVy { x, y } => xy(0x9000, x, y),
+            Instruction::LdIAddr { addr } => 0xA000 | addr & 0xFFF,
+            Instruction::JpV0Addr { addr } => 0xB000 | addr & 0xFFF,
+            Instruction::RndVxByte { x, byte } => xb(0xC000, x, byte),
+            Instruction::DrwVxVyNibble { x, y, nibble } => xy(0xD000, x, y) | nibble & 0xF,
+            Instruction::SkpVx { x } => x_only(0xE09E, x),
+            Instruction::SknpVx { x } => x_only(0xE0A1, x),
+            Instruction::LdVxDt { x } => x_only(0xF007, x),
+            Instruction::LdVxK { x } => x_only(0xF00A, x),
+            Instruction::LdDtVx { x } => x_only(0xF015, x),
+            Instruction::LdStVx { x } => x_only(0xF018, x),
+            Instruction::AddIVx { x } => x_only(0xF01E, x),
+            Instruction::LdFVx { x } => x_only(0xF029, x),
+            Instruction::LdBVx { x } => x_only(0xF033, x),
+            Instruction::LdIVx { x } => x_only(0xF055, x),
+            Instruction::LdVxI { x } => x_only(0xF065, x),
+        }
+    }
+
+    // Calls the trait method matching the instruction
+    pub fn visit<C: Chip8 + ?Sized>(&self, i: &mut C) {
+        match *self {
+            Instruction::Cls => i.cls(),
+            Instruction::Ret => i.ret(),
+            Instruction::SysAddr { addr } => i.sys_addr(addr),
+            Instruction::JpAddr { addr } => i.jp_addr(addr),
+            Instruction::CallAddr { addr } => i.call_addr(addr),
+            Instruction::SeVxByte { x, byte } => i.se_vx_byte(x, byte),
+            Instruction::SneVxByte { x, byte } => i.sne_vx_byte(x, byte),
+            Instruction::SeVxVy { x, y } => i.se_vx_vy(x, y),
+            Instruction::LdVxByte { x, byte } => i.ld_vx_byte(x, byte),
+            Instruction::AddVxByte { x, byte } => i.add_vx_byte(x, byte),
+            Instruction::LdVxVy { x, y } => i.ld_vx_vy(x, y),
+            Instruction::OrVxVy { x, y } => i.or_vx_vy(x, y),
+            Instruction::AndVxVy { x, y } => i.and_vx_vy(x, y),
+            Instruction::XorVxVy { x, y } => i.xor_vx_vy(x, y),
+            Instruction::AddVxVy { x, y } => i.add_vx_vy(x, y),
+            Instruction::SubVxVy { x, y } => i.sub_vx_vy(x, y),
+            Instruction::ShrVxVy { x, y } => i.shr_vx_vy(x, y),
+            Instruction::SubnVxVy { x, y } => i.subn_vx_vy(x, y),
+            Instruction::ShlVxVy { x, y } => i.shl_vx_vy(x, y),
+            Instruction::SneVxVy { x, y } => i.sne_vx_vy(x, y),
+            Instruction::LdIAddr { addr } => i.ld_i_addr(addr),
+            Instruction::JpV0Addr { addr } => i.jp_v0_addr(addr),
+            Instruction::RndVxByte { x, byte } => i.rnd_vx_byte(x, byte),
+            Instruction::DrwVxVyNibble { x, y, nibble } => i.drw_vx_vy_nibble(x, y, nibble),
+            Instruction::SkpVx { x } => i.skp_vx(x),
+            Instruction::SknpVx { x } => i.sknp_vx(x),
+            Instruction::LdVxDt { x } => i.ld_vx_dt(x),
+            Instruction::LdVxK { x } => i.ld_vx_k(x),
+            Instruction::LdDtVx { x } => i.ld_dt_vx(x),
+            Instruction::LdStVx { x } => i.ld_st_vx(x),
+            Instruction::AddIVx { x } => i.add_i_vx(x),
+            Instruction::LdFVx { x } => i.ld_f_vx(x),
+            Instruction::LdBVx { x } => i.ld_b_vx(x),
+            Instruction::LdIVx { x } => i.ld_i_vx(x),
+            Instruction::LdVxI { x } => i.ld_vx_i(x),
+        }
+    }
+}
+
+impl TryFrom<u16> for Instruction {
+    type Error = UnknownOpcode;
+
+    fn try_from(op: u16) -> Result<Self, Self::Error> {
+        Instruction::decode(op)
+    }
+}
+
+impl From<Instruction> for u16 {
+    fn from(instruction: Instruction) -> Self {
+        instruction.encode()
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Cls => write!(f, "CLS"),
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::SysAddr { addr } => write!(f, "SYS {:X}", addr),
+            Instruction::JpAddr { addr } => write!(f, "JP {:X}", addr),
+            Instruction::CallAddr { addr } => write!(f, "CALL {:X}", addr),
+            Instruction::SeVxByte { x, byte } => write!(f, "SE V{:X}, {:X}", x, byte),
+            Instruction::SneVxByte { x, byte } => write!(f, "SNE V{:X}, {:X}", x, byte),
+            Instruction::SeVxVy { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::LdVxByte { x, byte } => write!(f, "LD V{:X}, {:X}", x, byte),
+            Instruction::AddVxByte { x, byte } => write!(f, "ADD V{:X}, {:X}", x, byte),
+            Instruction::LdVxVy { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::OrVxVy { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::AndVxVy { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::XorVxVy { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddVxVy { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::SubVxVy { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShrVxVy { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubnVxVy { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShlVxVy { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SneVxVy { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LdIAddr { addr } => write!(f, "LD I, {:X}", addr),
+            Instruction::JpV0Addr { addr } => write!(f, "JP V0, {:X}", addr),
+            Instruction::RndVxByte { x, byte } => write!(f, "RND V{:X}, {:X}", x, byte),
+            Instruction::DrwVxVyNibble { x, y, nibble } => {
+                write!(f, "DRW V{:X}, V{:X}, {:X}", x, y, nibble)
+            }
+            Instruction::SkpVx { x } => write!(f, "SKP V{:X}", x),
+            Instruction::SknpVx { x } => write!(f, "SKNP V{:X}", x),
+            Instruction::LdVxDt { x } => write!(f, "LD V{:X}, DT", x),
+            Instruction::LdVxK { x } => write!(f, "LD V{:X}, K", x),
+            Instruction::LdDtVx { x } => write!(f, "LD DT, V{:X}", x),
+            Instruction::LdStVx { x } => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddIVx { x } => write!(f, "ADD I, V{:X}", x),
+            Instruction::LdFVx { x } => write!(f, "LD F, V{:X}", x),
+            Instruction::LdBVx { x } => write!(f, "LD B, V{:X}", x),
+            Instruction::LdIVx { x } => write!(f, "LD [I], V{:X}", x),
+            Instruction::LdVxI { x } => write!(f, "LD V{:X}, [I]", x),
+        }
+    }
+}
+
+// Collects the instruction decoded through the Chip8 trait
+struct Builder {
+    instruction: Result<Instruction, UnknownOpcode>,
+}
+
+impl Builder {
+    fn set(&mut self, instruction: Instruction) {
+        self.instruction = Ok(instruction);
+    }
+}
+
+impl Chip8 for Builder {
+    // 0
+    fn cls(&mut self) {
+        self.set(Instruction::Cls)
+    }
+    fn ret(&mut self) {
+        self.set(Instruction::Ret)
+    }
+    fn sys_addr(&mut self, addr: u16) {
+        self.set(Instruction::SysAddr { addr })
+    }
+    // 1
+    fn jp_addr(&mut self, addr: u16) {
+        self.set(Instruction::JpAddr { addr })
+    }
+    // 2
+    fn call_addr(&mut self, addr: u16) {
+        self.set(Instruction::CallAddr { addr })
+    }
+    // 3
+    fn se_vx_byte(&mut self, x: u8, byte: u8) {
+        self.set(Instruction::SeVxByte { x, byte })
+    }
+    // 4
+    fn sne_vx_byte(&mut self, x: u8, byte: u8) {
+        self.set(Instruction::SneVxByte { x, byte })
+    }
+    // 5
+    fn se_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::SeVxVy { x, y })
+    }
+    // 6
+    fn ld_vx_byte(&mut self, x: u8, byte: u8) {
+        self.set(Instruction::LdVxByte { x, byte })
+    }
+    // 7
+    fn add_vx_byte(&mut self, x: u8, byte: u8) {
+        self.set(Instruction::AddVxByte { x, byte })
+    }
+    // 8
+    fn ld_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::LdVxVy { x, y })
+    }
+    fn or_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::OrVxVy { x, y })
+    }
+    fn and_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::AndVxVy { x, y })
+    }
+    fn xor_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::XorVxVy { x, y })
+    }
+    fn add_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::AddVxVy { x, y })
+    }
+    fn sub_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::SubVxVy { x, y })
+    }
+    fn shr_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::ShrVxVy { x, y })
+    }
+    fn subn_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::SubnVxVy { x, y })
+    }
+    fn shl_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::ShlVxVy { x, y })
+    }
+    fn sne_vx_vy(&mut self, x: u8, y: u8) {
+        self.set(Instruction::SneVxVy { x, y })
+    }
+    // A
+    fn ld_i_addr(&mut self, addr: u16) {
+        self.set(Instruction::LdIAddr { addr })
+    }
+    // B
+    fn jp_v0_addr(&mut self, addr: u16) {
+        self.set(Instruction::JpV0Addr { addr })
+    }
+    // C
+    fn rnd_vx_byte(&mut self, x: u8, byte: u8) {
+        self.set(Instruction::RndVxByte { x, byte })
+    }
+    // D
+    fn drw_vx_vy_nibble(&mut self, x: u8, y: u8, nibble: u16) {
+        self.set(Instruction::DrwVxVyNibble { x, y, nibble })
+    }
+    // E
+    fn skp_vx(&mut self, x: u8) {
+        self.set(Instruction::SkpVx { x })
+    }
+    fn sknp_vx(&mut self, x: u8) {
+        self.set(Instruction::SknpVx { x })
+    }
+    // F
+    fn ld_vx_dt(&mut self, x: u8) {
+        self.set(Instruction::LdVxDt { x })
+    }
+    fn ld_vx_k(&mut self, x: u8) {
+        self.set(Instruction::LdVxK { x })
+    }
+    fn ld_dt_vx(&mut self, x: u8) {
+        self.set(Instruction::LdDtVx { x })
+    }
+    fn ld_st_vx(&mut self, x: u8) {
+        self.set(Instruction::LdStVx { x })
+    }
+    fn add_i_vx(&mut self, x: u8) {
+        self.set(Instruction::AddIVx { x })
+    }
+    fn ld_f_vx(&mut self, x: u8) {
+        self.set(Instruction::LdFVx { x })
+    }
+    fn ld_b_vx(&mut self, x: u8) {
+        self.set(Instruction::LdBVx { x })
+    }
+    fn ld_i_vx(&mut self, x: u8) {
+        self.set(Instruction::LdIVx { x })
+    }
+    fn ld_vx_i(&mut self, x: u8) {
+        self.set(Instruction::LdVxI { x })
+    }
+
+    fn unknown(&mut self, op: u16) {
+        self.instruction = Err(UnknownOpcode(op));
+    }
+}
@@ -1,29 +1,314 @@
+use crate::chip8::cdp1802::{self, Fault};
 use crate::chip8::cpu::Cpu;
 use crate::chip8::decoder::decode;
-use crate::chip8::instructions::Chip8;
-use crate::chip8::program::Program;
+use crate::chip8::instructions::{Chip8, Instruction};
+use crate::chip8::platform::{Platform, Quirks};
+use crate::chip8::program::{LoadError, Program};
+use crate::chip8::sys::SysPolicy;
+use crate::chip8::timing::{self, VipClock};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
 
 pub struct Interpreter {
     pub cpu: Cpu,
+    // decoded instructions by address, only when the cache is enabled
+    cache: Option<Vec<Option<Instruction>>>,
+    // machine cycles of the COSMAC VIP, only in cycle accurate mode
+    pub clock: Option<VipClock>,
+    pub quirks: Quirks,
+    // instructions per frame when not in cycle accurate mode
+    pub tickrate: u32,
+    // the timers do not tick while FX0A waits for a key
+    pub pause_timers_on_key_wait: bool,
+    key_wait: Option<KeyWait>,
+    pub sys: SysPolicy,
+    // CXNN, seeded to replay a run or given by the platform with set_rng
+    rng: Box<dyn RngCore + Send>,
+}
+
+// Why an instruction could not be executed, from `Interpreter::try_step`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    UnknownOpcode(u16),
+    MachineCode(u16),
+    Native(u16, Fault),
+    StackOverflow,
+    StackUnderflow,
+    PcOutOfBounds(u16),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownOpcode(op) => write!(f, "unknown opcode {:04X}", op),
+            Error::MachineCode(addr) => write!(f, "no handler for the routine at {:03X}", addr),
+            Error::Native(addr, fault) => write!(f, "routine at {:03X} : {}", addr, fault),
+            Error::StackOverflow => write!(f, "call with a full stack"),
+            Error::StackUnderflow => write!(f, "return with an empty stack"),
+            Error::PcOutOfBounds(pc) => write!(f, "program counter {:X} out of memory", pc),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+// FX0A waits for a key to be pressed then released, as on the VIP, keys
+// already down when it starts have to be released first
+#[derive(Clone, Copy)]
+struct KeyWait {
+    held: [bool; 16],
+    pressed: Option<usize>,
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "none")))]
+fn entropy_rng() -> StdRng {
+    StdRng::from_entropy()
+}
+
+// rand has no entropy source on wasm32-unknown-unknown and bare metal, the
+// page calls seed_rng and a microcontroller set_rng with its own generator
+#[cfg(any(target_arch = "wasm32", target_os = "none"))]
+fn entropy_rng() -> StdRng {
+    StdRng::seed_from_u64(0)
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        Self { cpu: Cpu::new() }
+        Self {
+            cpu: Cpu::new(),
+            cache: None,
+            clock: None,
+            quirks: Platform::Chip8.quirks(),
+            tickrate: 1,
+            pause_timers_on_key_wait: false,
+            key_wait: None,
+            sys: SysPolicy::Error,
+            rng: Box::new(entropy_rng()),
+        }
     }
 
-    pub fn load_program(&mut self, p: &Program) {
-        self.cpu.load_program(p);
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.set_rng(StdRng::seed_from_u64(seed));
     }
 
-    pub fn step(&mut self) {
+    pub fn set_rng(&mut self, rng: impl RngCore + Send + 'static) {
+        self.rng = Box::new(rng);
+    }
+
+    pub fn with_cache() -> Self {
+        Self {
+            cache: Some(vec![None; 4096]),
+            ..Self::new()
+        }
+    }
+
+    pub fn set_cycle_accurate(&mut self, on: bool) {
+        self.clock = if on { Some(VipClock::new()) } else { None };
+    }
+
+    pub fn load_program(&mut self, p: &Program) -> Result<(), LoadError> {
+        self.cpu.load_program(p)?;
+        self.flush_cache();
+        // a FX0A of the previous program does not wait in the new one
+        self.key_wait = None;
+        Ok(())
+    }
+
+    // To be called after writing into cpu.memory from outside of the interpreter
+    pub fn flush_cache(&mut self) {
+        if let Some(cache) = &mut self.cache {
+            cache.fill(None);
+        }
+    }
+
+    fn invalidate(&mut self, addr: usize) {
+        if let Some(cache) = &mut self.cache {
+            // the instruction starting on the previous byte covers addr too
+            cache[addr] = None;
+            if addr > 0 {
+                cache[addr - 1] = None;
+            }
+        }
+    }
+
+    fn fetch(&mut self) -> u16 {
         let mut op: u16 = self.cpu.memory[self.cpu.program_counter as usize] as u16;
         self.cpu.program_counter += 1;
         op <<= 8;
         op |= self.cpu.memory[self.cpu.program_counter as usize] as u16;
         self.cpu.program_counter += 1;
+        op
+    }
+
+    // Runs the instructions of one 60 Hz frame then ticks the timers, `tickrate`
+    // instructions unless in cycle accurate mode
+    pub fn run_frame(&mut self) {
+        if let Err(e) = self.try_run_frame() {
+            panic!("{}", e);
+        }
+    }
+
+    // Same as run_frame but stops on the first instruction that cannot be
+    // executed, the timers do not tick then
+    pub fn try_run_frame(&mut self) -> Result<(), Error> {
+        if self.clock.is_some() {
+            self.run_vip_frame()?;
+        } else {
+            for i in 0..self.tickrate {
+                if self.waits_display(i == 0) {
+                    break;
+                }
+                self.try_step()?;
+            }
+        }
+        self.tick_timers();
+        Ok(())
+    }
+
+    pub fn waiting_for_key(&self) -> bool {
+        self.key_wait.is_some()
+    }
+
+    pub fn tick_timers(&mut self) {
+        if self.pause_timers_on_key_wait && self.waiting_for_key() {
+            return;
+        }
+        if self.cpu.delay_timer > 0 {
+            self.cpu.delay_timer -= 1;
+        }
+        if self.cpu.sound_timer > 0 {
+            self.cpu.sound_timer -= 1;
+        }
+    }
+
+    fn run_vip_frame(&mut self) -> Result<(), Error> {
+        let mut clock = self.clock.take().unwrap();
+        clock.budget += timing::CYCLES_PER_FRAME - timing::INTERRUPT_CYCLES;
+
+        let mut frame_start = true;
+        let mut result = Ok(());
+        while clock.budget > 0 {
+            if self.waits_display(frame_start) {
+                // the cycles left until the interrupt are lost
+                clock.budget = 0;
+                break;
+            }
+            // try_step reports the opcodes that are not instructions
+            if let Some(Ok(instruction)) = self.peek().map(Instruction::decode) {
+                clock.budget -= timing::cycles(&instruction, &self.cpu);
+            }
+            result = self.try_step();
+            if result.is_err() {
+                break;
+            }
+            frame_start = false;
+        }
+
+        self.clock = Some(clock);
+        result
+    }
+
+    // With the display wait quirk a DXYN waits for the vertical blank, it is
+    // only executed as the first instruction of a frame
+    pub fn waits_display(&self, frame_start: bool) -> bool {
+        self.quirks.display_wait
+            && !frame_start
+            && self.peek().is_some_and(|op| op & 0xF000 == 0xD000)
+    }
 
-        decode(op, self);
+    // The instruction at the program counter without moving it, None when
+    // the program counter is past the last word of memory
+    pub fn peek(&self) -> Option<u16> {
+        let pc = self.cpu.program_counter as usize;
+        let bytes = self.cpu.memory.get(pc..pc + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    // The address I + offset, wrapping around the 4K of memory
+    fn at_index(&self, offset: u16) -> usize {
+        (self.cpu.index.wrapping_add(offset) & 0x0FFF) as usize
+    }
+
+    // Runs SYS addr according to the policy
+    fn sys_call(&mut self, addr: u16) -> Result<(), Error> {
+        let result = match &self.sys {
+            SysPolicy::Ignore => return Ok(()),
+            SysPolicy::Error => return Err(Error::MachineCode(addr)),
+            SysPolicy::Hle(routines) => {
+                let routine = routines.get(addr).ok_or(Error::MachineCode(addr))?;
+                routine(&mut self.cpu);
+                Ok(())
+            }
+            SysPolicy::Native => {
+                cdp1802::call(&mut self.cpu, addr).map_err(|fault| Error::Native(addr, fault))
+            }
+        };
+        // the routine may have written anywhere
+        self.flush_cache();
+        result
+    }
+
+    // Same as step but returns an error instead of panicking, the state is
+    // left untouched when the instruction cannot be executed
+    pub fn try_step(&mut self) -> Result<(), Error> {
+        match self.decoded()? {
+            Instruction::SysAddr { addr } if !self.sys.handles(addr) => {
+                return Err(Error::MachineCode(addr))
+            }
+            // a native routine can fail after changing the state
+            Instruction::SysAddr { addr } => {
+                self.cpu.program_counter += 2;
+                return self.sys_call(addr);
+            }
+            Instruction::CallAddr { .. } if self.cpu.stack_pointer + 1 >= self.cpu.stack.len() => {
+                return Err(Error::StackOverflow)
+            }
+            Instruction::Ret if self.cpu.stack_pointer == 0 => return Err(Error::StackUnderflow),
+            _ => {}
+        }
+        self.step();
+        Ok(())
+    }
+
+    pub fn step(&mut self) {
+        if self.cache.is_none() {
+            let op = self.fetch();
+            decode(op, self);
+            return;
+        }
+
+        let decoded = self.decoded();
+        self.cpu.program_counter += 2;
+        match decoded {
+            Ok(instruction) => instruction.visit(self),
+            Err(Error::UnknownOpcode(op)) => self.unknown(op),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    // The instruction at the program counter, from the cache when enabled
+    fn decoded(&mut self) -> Result<Instruction, Error> {
+        let pc = self.cpu.program_counter;
+        let cached = self.cache.as_ref().and_then(|cache| cache.get(pc as usize));
+        if let Some(Some(instruction)) = cached {
+            return Ok(*instruction);
+        }
+        let op = self.peek().ok_or(Error::PcOutOfBounds(pc))?;
+        let instruction = Instruction::decode(op).map_err(|_| Error::UnknownOpcode(op))?;
+        if let Some(cache) = &mut self.cache {
+            cache[pc as usize] = Some(instruction);
+        }
+        Ok(instruction)
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -37,7 +322,9 @@ impl Chip8 for Interpreter {
         self.cpu.stack_pointer -= 1;
     }
     fn sys_addr(&mut self, addr: u16) {
-        panic!("unimplemented SYS {:X}", addr);
+        if let Err(e) = self.sys_call(addr) {
+            panic!("SYS {:X} : {}", addr, e);
+        }
     }
     // 1
     fn jp_addr(&mut self, addr: u16) {
@@ -83,52 +370,56 @@ impl Chip8 for Interpreter {
     }
     fn or_vx_vy(&mut self, x: u8, y: u8) {
         self.cpu.registers[x as usize] |= self.cpu.registers[y as usize];
+        if self.quirks.vf_reset {
+            self.cpu.registers[0x0F] = 0;
+        }
     }
     fn and_vx_vy(&mut self, x: u8, y: u8) {
         self.cpu.registers[x as usize] &= self.cpu.registers[y as usize];
+        if self.quirks.vf_reset {
+            self.cpu.registers[0x0F] = 0;
+        }
     }
     fn xor_vx_vy(&mut self, x: u8, y: u8) {
         self.cpu.registers[x as usize] ^= self.cpu.registers[y as usize];
-    }
-    fn add_vx_vy(&mut self, x: u8, y: u8) {
-        let result =
-            (self.cpu.registers[x as usize] as u16) + (self.cpu.registers[y as usize] as u16);
-        if result > 0xFF {
-            self.cpu.registers[0x0F] = 1; // VF is set to 1 to carry the overflow
-        } else {
+        if self.quirks.vf_reset {
             self.cpu.registers[0x0F] = 0;
         }
-        self.cpu.registers[x as usize] = result as u8;
+    }
+    // the flag is written after the result, so it wins when VF is the destination
+    fn add_vx_vy(&mut self, x: u8, y: u8) {
+        let (result, carry) =
+            self.cpu.registers[x as usize].overflowing_add(self.cpu.registers[y as usize]);
+        self.cpu.registers[x as usize] = result;
+        self.cpu.registers[0x0F] = carry as u8; // VF is set to 1 to carry the overflow
     }
     fn sub_vx_vy(&mut self, x: u8, y: u8) {
-        let result =
-            (self.cpu.registers[x as usize] as i16) - (self.cpu.registers[y as usize] as i16);
-        if self.cpu.registers[x as usize] > self.cpu.registers[y as usize] {
-            self.cpu.registers[0x0F] = 0;
-        } else {
-            self.cpu.registers[0x0F] = 1;
-        }
-        self.cpu.registers[0x0F] = result as u8;
+        let (result, borrow) =
+            self.cpu.registers[x as usize].overflowing_sub(self.cpu.registers[y as usize]);
+        self.cpu.registers[x as usize] = result;
+        self.cpu.registers[0x0F] = !borrow as u8; // VF is set to 0 when there is a borrow
     }
     fn shr_vx_vy(&mut self, x: u8, y: u8) {
-        self.cpu.registers[x as usize] = self.cpu.registers[y as usize];
-        self.cpu.registers[0x0F] = self.cpu.registers[x as usize] & 0x01;
+        if !self.quirks.shifting {
+            self.cpu.registers[x as usize] = self.cpu.registers[y as usize];
+        }
+        let flag = self.cpu.registers[x as usize] & 0x01;
         self.cpu.registers[x as usize] >>= 1;
+        self.cpu.registers[0x0F] = flag;
     }
     fn subn_vx_vy(&mut self, x: u8, y: u8) {
-        let result =
-            (self.cpu.registers[y as usize] as i16) - (self.cpu.registers[x as usize] as i16);
-        if self.cpu.registers[x as usize] > self.cpu.registers[y as usize] {
-            self.cpu.registers[0x0F] = 1;
-        } else {
-            self.cpu.registers[0x0F] = 0;
-        }
-        self.cpu.registers[x as usize] = result as u8;
+        let (result, borrow) =
+            self.cpu.registers[y as usize].overflowing_sub(self.cpu.registers[x as usize]);
+        self.cpu.registers[x as usize] = result;
+        self.cpu.registers[0x0F] = !borrow as u8;
     }
     fn shl_vx_vy(&mut self, x: u8, y: u8) {
-        self.cpu.registers[x as usize] = self.cpu.registers[y as usize];
-        self.cpu.registers[0x0F] = self.cpu.registers[x as usize] & 0x80;
+        if !self.quirks.shifting {
+            self.cpu.registers[x as usize] = self.cpu.registers[y as usize];
+        }
+        let flag = self.cpu.registers[x as usize] >> 7;
         self.cpu.registers[x as usize] <<= 1;
+        self.cpu.registers[0x0F] = flag;
     }
     fn sne_vx_vy(&mut self, x: u8, y: u8) {
         if self.cpu.registers[y as usize] != self.cpu.registers[x as usize] {
@@ -141,11 +432,17 @@ impl Chip8 for Interpreter {
     }
     // B
     fn jp_v0_addr(&mut self, addr: u16) {
-        self.cpu.program_counter = addr + (self.cpu.registers[0] as u16);
+        // with the jumping quirk BXNN adds VX instead of V0
+        let x = if self.quirks.jumping {
+            (addr >> 8) as usize
+        } else {
+            0
+        };
+        self.cpu.program_counter = addr + (self.cpu.registers[x] as u16);
     }
     // C
     fn rnd_vx_byte(&mut self, x: u8, byte: u8) {
-        let random_value = rand::random::<u8>();
+        let random_value = self.rng.gen::<u8>();
         self.cpu.registers[x as usize] = byte & random_value;
     }
     // D
@@ -156,9 +453,9 @@ impl Chip8 for Interpreter {
 
         // println!("{op:X} | y : {y} | x : {x}");
 
-        let sprite_start = self.cpu.index as usize;
-        let sprite_end = sprite_start + nibble as usize;
-        let sprite = &self.cpu.memory[sprite_start..sprite_end];
+        let sprite: Vec<u8> = (0..nibble)
+            .map(|i| self.cpu.memory[self.at_index(i)])
+            .collect();
 
         for row in sprite {
             let mut row_x = x;
@@ -176,23 +473,29 @@ impl Chip8 for Interpreter {
                 }
                 row_x += 1;
                 if row_x >= 64 {
-                    break;
+                    if self.quirks.clipping {
+                        break;
+                    }
+                    row_x = 0;
                 }
             }
             y += 1;
             if y >= 32 {
-                break;
+                if self.quirks.clipping {
+                    break;
+                }
+                y = 0;
             }
         }
     }
     // E
     fn skp_vx(&mut self, x: u8) {
-        if self.cpu.keys[self.cpu.registers[x as usize] as usize] {
+        if self.cpu.keys[(self.cpu.registers[x as usize] & 0x0F) as usize] {
             self.cpu.program_counter += 2;
         }
     }
     fn sknp_vx(&mut self, x: u8) {
-        if !self.cpu.keys[self.cpu.registers[x as usize] as usize] {
+        if !self.cpu.keys[(self.cpu.registers[x as usize] & 0x0F) as usize] {
             self.cpu.program_counter += 2;
             // println!("{op:X} | SKP | Vx : {} | skipped", self.cpu.registers[x]);
         }
@@ -202,16 +505,30 @@ impl Chip8 for Interpreter {
         self.cpu.registers[x as usize] = self.cpu.delay_timer;
     }
     fn ld_vx_k(&mut self, x: u8) {
-        let mut pressed = false;
-        for k in 0..16 {
-            if self.cpu.keys[k] {
+        let keys = self.cpu.keys;
+        let mut wait = self.key_wait.unwrap_or(KeyWait {
+            held: keys,
+            pressed: None,
+        });
+
+        match wait.pressed {
+            Some(k) if !keys[k] => {
                 self.cpu.registers[x as usize] = k as u8;
-                pressed = true;
+                self.key_wait = None;
+                return;
+            }
+            Some(_) => {}
+            None => {
+                for (held, down) in wait.held.iter_mut().zip(keys) {
+                    *held &= down;
+                }
+                wait.pressed = (0..16).find(|k| keys[*k] && !wait.held[*k]);
             }
         }
-        if !pressed {
-            self.cpu.program_counter -= 2;
-        }
+
+        // executed again until the key is released
+        self.key_wait = Some(wait);
+        self.cpu.program_counter -= 2;
     }
     fn ld_dt_vx(&mut self, x: u8) {
         self.cpu.delay_timer = self.cpu.registers[x as usize];
@@ -220,27 +537,41 @@ impl Chip8 for Interpreter {
         self.cpu.sound_timer = self.cpu.registers[x as usize];
     }
     fn add_i_vx(&mut self, x: u8) {
-        self.cpu.index += self.cpu.registers[x as usize] as u16;
+        self.cpu.index = self
+            .cpu
+            .index
+            .wrapping_add(self.cpu.registers[x as usize] as u16);
     }
     fn ld_f_vx(&mut self, x: u8) {
-        self.cpu.index = (x * 0x05) as u16;
+        self.cpu.index = (self.cpu.registers[x as usize] & 0x0F) as u16 * 0x05;
     }
     fn ld_b_vx(&mut self, x: u8) {
-        self.cpu.memory[self.cpu.index as usize] = self.cpu.registers[x as usize] / 100;
-        self.cpu.memory[(self.cpu.index + 1) as usize] =
-            (self.cpu.registers[x as usize] - self.cpu.memory[self.cpu.index as usize] * 100) / 10;
-        self.cpu.memory[(self.cpu.index + 2) as usize] = self.cpu.registers[x as usize]
-            - self.cpu.memory[self.cpu.index as usize] * 100
-            - self.cpu.memory[(self.cpu.index + 1) as usize] * 10;
+        let value = self.cpu.registers[x as usize];
+        for (i, digit) in [value / 100, value / 10 % 10, value % 10]
+            .into_iter()
+            .enumerate()
+        {
+            let addr = self.at_index(i as u16);
+            self.invalidate(addr);
+            self.cpu.memory[addr] = digit;
+        }
     }
     fn ld_i_vx(&mut self, x: u8) {
         for i in 0..(x + 1) {
-            self.cpu.memory[(self.cpu.index + i as u16) as usize] = self.cpu.registers[i as usize];
+            let addr = self.at_index(i as u16);
+            self.invalidate(addr);
+            self.cpu.memory[addr] = self.cpu.registers[i as usize];
+        }
+        if self.quirks.memory {
+            self.cpu.index = self.cpu.index.wrapping_add(x as u16 + 1);
         }
     }
     fn ld_vx_i(&mut self, x: u8) {
         for i in 0..(x + 1) {
-            self.cpu.registers[i as usize] = self.cpu.memory[(self.cpu.index + i as u16) as usize];
+            self.cpu.registers[i as usize] = self.cpu.memory[self.at_index(i as u16)];
+        }
+        if self.quirks.memory {
+            self.cpu.index = self.cpu.index.wrapping_add(x as u16 + 1);
         }
     }
 
@@ -0,0 +1,397 @@
+use crate::chip8::instructions::Instruction;
+use crate::chip8::interpreter::Interpreter;
+use crate::chip8::platform::Quirks;
+use crate::chip8::program::{LoadError, Program};
+use std::ptr;
+
+// executions of an address before its block gets compiled
+const HOT_THRESHOLD: u8 = 8;
+// writes into an address after which it is left to the interpreter
+const SELF_MODIFYING_LIMIT: u8 = 4;
+const MAX_BLOCK_LEN: u32 = 64;
+const CODE_SIZE: usize = 1 << 20;
+
+// offsets inside Context, used by the generated code
+const INDEX: u8 = 16;
+const DELAY_TIMER: u8 = 18;
+const SOUND_TIMER: u8 = 19;
+const VF: u8 = 15;
+
+// The part of the cpu the generated code reads and writes, rdi points to it
+#[repr(C)]
+struct Context {
+    registers: [u8; 16],
+    index: u16,
+    delay_timer: u8,
+    sound_timer: u8,
+}
+
+#[derive(Clone, Copy)]
+struct Block {
+    offset: usize,
+    end: u16,
+    // instructions executed by any path through the block
+    steps: u32,
+}
+
+// A region of memory that is either writable or executable, never both
+struct ExecMemory {
+    ptr: *mut u8,
+    used: usize,
+}
+
+impl ExecMemory {
+    fn new() -> Self {
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                CODE_SIZE,
+                libc::PROT_READ | libc::PROT_EXEC,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            panic!("unable to map executable memory");
+        }
+        Self {
+            ptr: ptr as *mut u8,
+            used: 0,
+        }
+    }
+
+    fn push(&mut self, code: &[u8]) -> Option<usize> {
+        if self.used + code.len() > CODE_SIZE {
+            return None;
+        }
+        let offset = self.used;
+        unsafe {
+            self.protect(libc::PROT_READ | libc::PROT_WRITE);
+            ptr::copy_nonoverlapping(code.as_ptr(), self.ptr.add(offset), code.len());
+            self.protect(libc::PROT_READ | libc::PROT_EXEC);
+        }
+        self.used += code.len();
+        Some(offset)
+    }
+
+    unsafe fn protect(&self, prot: libc::c_int) {
+        if libc::mprotect(self.ptr as *mut libc::c_void, CODE_SIZE, prot) != 0 {
+            panic!("unable to change the protection of the code memory");
+        }
+    }
+
+    fn reset(&mut self) {
+        self.used = 0;
+    }
+
+    unsafe fn call(&self, offset: usize, context: *mut Context) -> u16 {
+        let f: extern "sysv64" fn(*mut Context) -> u32 =
+            std::mem::transmute(self.ptr.add(offset));
+        f(context) as u16
+    }
+}
+
+impl Drop for ExecMemory {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, CODE_SIZE);
+        }
+    }
+}
+
+// Runs the program through compiled x86-64 blocks, the interpreter executes
+// everything the compiler does not translate
+pub struct Jit {
+    pub interpreter: Interpreter,
+    code: ExecMemory,
+    blocks: Vec<Option<Block>>,
+    heat: Vec<u8>,
+    writes: Vec<u8>,
+}
+
+impl Jit {
+    pub fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            code: ExecMemory::new(),
+            blocks: vec![None; 4096],
+            heat: vec![0; 4096],
+            writes: vec![0; 4096],
+        }
+    }
+
+    pub fn load_program(&mut self, p: &Program) -> Result<(), LoadError> {
+        self.interpreter.load_program(p)?;
+        self.flush();
+        Ok(())
+    }
+
+    // To be called after writing into cpu.memory or changing the quirks from outside of the jit
+    pub fn flush(&mut self) {
+        self.blocks.fill(None);
+        self.heat.fill(0);
+        self.code.reset();
+    }
+
+    // Executes exactly `steps` instructions
+    pub fn run(&mut self, steps: u64) {
+        let mut remaining = steps;
+        while remaining > 0 {
+            remaining -= self.step(remaining.min(u32::MAX as u64) as u32) as u64;
+        }
+    }
+
+    // Executes a compiled block or a single instruction without going over
+    // `budget` instructions, returns the number of instructions executed
+    pub fn step(&mut self, budget: u32) -> u32 {
+        let pc = self.interpreter.cpu.program_counter as usize;
+        if pc + 1 < 4096 {
+            if self.blocks[pc].is_none() && self.heat[pc] >= HOT_THRESHOLD {
+                self.blocks[pc] = self.compile(pc as u16);
+                if self.blocks[pc].is_none() {
+                    // the first instruction is left to the interpreter, do not
+                    // try again before it gets hot again
+                    self.heat[pc] = 0;
+                }
+            }
+            if let Some(block) = self.blocks[pc] {
+                if block.steps <= budget {
+                    self.execute(block);
+                    return block.steps;
+                }
+            }
+            self.heat[pc] = self.heat[pc].saturating_add(1);
+        }
+
+        self.interpret();
+        1
+    }
+
+    fn execute(&mut self, block: Block) {
+        let cpu = &mut self.interpreter.cpu;
+        let mut context = Context {
+            registers: cpu.registers,
+            index: cpu.index,
+            delay_timer: cpu.delay_timer,
+            sound_timer: cpu.sound_timer,
+        };
+        let pc = unsafe { self.code.call(block.offset, &mut context) };
+        cpu.registers = context.registers;
+        cpu.index = context.index;
+        cpu.delay_timer = context.delay_timer;
+        cpu.sound_timer = context.sound_timer;
+        cpu.program_counter = pc;
+    }
+
+    fn interpret(&mut self) {
+        let cpu = &self.interpreter.cpu;
+        let pc = cpu.program_counter as usize;
+        let instruction = fetch(&cpu.memory, pc).map(Instruction::decode);
+        // the only instructions writing into memory
+        let written = match instruction {
+            Some(Ok(Instruction::LdBVx { .. })) => Some((cpu.index, 3)),
+            Some(Ok(Instruction::LdIVx { x })) => Some((cpu.index, x as u16 + 1)),
+            _ => None,
+        };
+
+        self.interpreter.step();
+
+        // a machine code routine may have written anywhere
+        if let Some(Ok(Instruction::SysAddr { .. })) = instruction {
+            self.flush();
+            return;
+        }
+
+        // the writes wrap around the end of memory
+        if let Some((start, len)) = written {
+            let start = start & 0x0FFF;
+            let end = start + len;
+            self.invalidate(start, end.min(4096));
+            if end > 4096 {
+                self.invalidate(0, end - 4096);
+            }
+        }
+    }
+
+    fn invalidate(&mut self, start: u16, end: u16) {
+        let end = (end as usize).min(4096);
+        for addr in (start as usize)..end {
+            self.writes[addr] = self.writes[addr].saturating_add(1);
+        }
+        let first = (start as usize).saturating_sub(2 * MAX_BLOCK_LEN as usize);
+        for addr in first..end {
+            if let Some(block) = self.blocks[addr] {
+                if block.end as usize > start as usize {
+                    self.blocks[addr] = None;
+                }
+            }
+        }
+    }
+
+    fn compile(&mut self, start: u16) -> Option<Block> {
+        let memory = &self.interpreter.cpu.memory;
+        let mut code = Vec::new();
+        let mut pc = start;
+        let mut steps = 0;
+
+        loop {
+            let addr = pc as usize;
+            let instruction = match fetch(memory, addr).map(Instruction::decode) {
+                Some(Ok(instruction)) if steps < MAX_BLOCK_LEN => instruction,
+                _ => break,
+            };
+            if self.writes[addr] >= SELF_MODIFYING_LIMIT
+                || self.writes[addr + 1] >= SELF_MODIFYING_LIMIT
+            {
+                break;
+            }
+            if !emit(&mut code, pc, instruction, &self.interpreter.quirks) {
+                break;
+            }
+            steps += 1;
+            pc += 2;
+            if ends_block(instruction) {
+                let offset = self.push(&code)?;
+                return Some(Block {
+                    offset,
+                    end: pc,
+                    steps,
+                });
+            }
+        }
+
+        if steps == 0 {
+            return None;
+        }
+        exit(&mut code, pc);
+        let offset = self.push(&code)?;
+        Some(Block {
+            offset,
+            end: pc,
+            steps,
+        })
+    }
+
+    fn push(&mut self, code: &[u8]) -> Option<usize> {
+        match self.code.push(code) {
+            Some(offset) => Some(offset),
+            None => {
+                // out of space, start over
+                self.blocks.fill(None);
+                self.code.reset();
+                self.code.push(code)
+            }
+        }
+    }
+}
+
+impl Default for Jit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn fetch(memory: &[u8; 4096], addr: usize) -> Option<u16> {
+    if addr + 1 < memory.len() {
+        Some(((memory[addr] as u16) << 8) | memory[addr + 1] as u16)
+    } else {
+        None
+    }
+}
+
+fn ends_block(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::JpAddr { .. }
+            | Instruction::SeVxByte { .. }
+            | Instruction::SneVxByte { .. }
+            | Instruction::SeVxVy { .. }
+            | Instruction::SneVxVy { .. }
+    )
+}
+
+// mov eax, pc ; ret
+fn exit(code: &mut Vec<u8>, pc: u16) {
+    code.push(0xB8);
+    code.extend_from_slice(&(pc as u32).to_le_bytes());
+    code.push(0xC3);
+}
+
+// jcc over the first exit : skip to pc + 4 when the condition holds
+fn skip(code: &mut Vec<u8>, jcc: u8, pc: u16) {
+    code.extend_from_slice(&[jcc, 0x06]);
+    exit(code, pc + 4);
+    exit(code, pc + 2);
+}
+
+// mov al, [rdi + y] ; op [rdi + x], al, then mov byte [rdi + 15], 0 with the vf_reset quirk
+fn logic(code: &mut Vec<u8>, op: u8, x: u8, y: u8, quirks: &Quirks) {
+    code.extend_from_slice(&[0x8A, 0x47, y, op, 0x47, x]);
+    if quirks.vf_reset {
+        code.extend_from_slice(&[0xC6, 0x47, VF, 0x00]);
+    }
+}
+
+// Appends the x86-64 code of an instruction, false when it must be interpreted
+fn emit(code: &mut Vec<u8>, pc: u16, instruction: Instruction, quirks: &Quirks) -> bool {
+    const JE: u8 = 0x74;
+    const JNE: u8 = 0x75;
+
+    match instruction {
+        Instruction::JpAddr { addr } => exit(code, addr),
+        Instruction::SeVxByte { x, byte } => {
+            // cmp byte [rdi + x], byte
+            code.extend_from_slice(&[0x80, 0x7F, x, byte]);
+            skip(code, JNE, pc);
+        }
+        Instruction::SneVxByte { x, byte } => {
+            code.extend_from_slice(&[0x80, 0x7F, x, byte]);
+            skip(code, JE, pc);
+        }
+        Instruction::SeVxVy { x, y } => {
+            // mov al, [rdi + x] ; cmp al, [rdi + y]
+            code.extend_from_slice(&[0x8A, 0x47, x, 0x3A, 0x47, y]);
+            skip(code, JNE, pc);
+        }
+        Instruction::SneVxVy { x, y } => {
+            code.extend_from_slice(&[0x8A, 0x47, x, 0x3A, 0x47, y]);
+            skip(code, JE, pc);
+        }
+        // mov byte [rdi + x], byte
+        Instruction::LdVxByte { x, byte } => code.extend_from_slice(&[0xC6, 0x47, x, byte]),
+        // add byte [rdi + x], byte
+        Instruction::AddVxByte { x, byte } => code.extend_from_slice(&[0x80, 0x47, x, byte]),
+        // mov al, [rdi + y] ; op [rdi + x], al
+        Instruction::LdVxVy { x, y } => code.extend_from_slice(&[0x8A, 0x47, y, 0x88, 0x47, x]),
+        Instruction::OrVxVy { x, y } => logic(code, 0x08, x, y, quirks),
+        Instruction::AndVxVy { x, y } => logic(code, 0x20, x, y, quirks),
+        Instruction::XorVxVy { x, y } => logic(code, 0x30, x, y, quirks),
+        Instruction::AddVxVy { x, y } => code.extend_from_slice(&[
+            // mov al, [rdi + x] ; add al, [rdi + y] ; setc cl
+            0x8A, 0x47, x, 0x02, 0x47, y, 0x0F, 0x92, 0xC1,
+            // mov [rdi + x], al ; mov [rdi + 15], cl
+            0x88, 0x47, x, 0x88, 0x4F, VF,
+        ]),
+        Instruction::LdIAddr { addr } => {
+            // mov word [rdi + 16], addr
+            code.extend_from_slice(&[0x66, 0xC7, 0x47, INDEX]);
+            code.extend_from_slice(&addr.to_le_bytes());
+        }
+        // movzx eax, byte [rdi + x] ; add [rdi + 16], ax
+        Instruction::AddIVx { x } => {
+            code.extend_from_slice(&[0x0F, 0xB6, 0x47, x, 0x66, 0x01, 0x47, INDEX])
+        }
+        Instruction::LdVxDt { x } => {
+            code.extend_from_slice(&[0x8A, 0x47, DELAY_TIMER, 0x88, 0x47, x])
+        }
+        Instruction::LdDtVx { x } => {
+            code.extend_from_slice(&[0x8A, 0x47, x, 0x88, 0x47, DELAY_TIMER])
+        }
+        Instruction::LdStVx { x } => {
+            code.extend_from_slice(&[0x8A, 0x47, x, 0x88, 0x47, SOUND_TIMER])
+        }
+        _ => return false,
+    }
+    true
+}
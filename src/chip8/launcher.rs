@@ -0,0 +1,100 @@
+use crate::chip8::archive::{self, Rom, EXTENSIONS};
+use crate::chip8::database::{Database, Entry};
+use crate::chip8::interpreter::Interpreter;
+use crate::chip8::program::{LoadError, Program};
+use crate::screen::Screen;
+use std::path::Path;
+
+// The frames run before taking the thumbnail, most games drew their title or
+// their first level after two seconds
+pub const THUMBNAIL_FRAMES: u64 = 120;
+// The tickrate of the thumbnails when the database gives none, the 1 of the
+// interpreter draws little in that time
+const THUMBNAIL_TICKRATE: u32 = 20;
+
+// The roms of a directory with their title and a thumbnail, what the SDL
+// launcher draws
+pub struct Launcher {
+    pub roms: Vec<Rom>,
+    // the title of the database or the file name
+    pub titles: Vec<String>,
+    pub entries: Vec<Option<Entry>>,
+    pub thumbnails: Vec<Screen>,
+}
+
+impl Launcher {
+    // The roms of a directory and of the archives in it
+    pub fn scan(dir: impl AsRef<Path>, database: Option<&Database>) -> Result<Self, LoadError> {
+        let roms: Vec<Rom> = archive::open(dir)?
+            .into_iter()
+            // the files of the archives are not filtered by archive::open
+            .filter(|rom| archive::has_extension(&rom.name, &EXTENSIONS))
+            .collect();
+        if roms.is_empty() {
+            return Err(LoadError::NoRom);
+        }
+        Ok(Self::new(roms, database))
+    }
+
+    pub fn new(roms: Vec<Rom>, database: Option<&Database>) -> Self {
+        let entries: Vec<Option<Entry>> = roms
+            .iter()
+            .map(|rom| database.and_then(|d| d.lookup(&rom.program)))
+            .collect();
+        let titles = roms
+            .iter()
+            .zip(&entries)
+            .map(|(rom, entry)| entry.as_ref().map_or(&rom.name, |e| &e.title).clone())
+            .collect();
+        let thumbnails = roms
+            .iter()
+            .zip(&entries)
+            .map(|(rom, entry)| thumbnail(&rom.program, entry.as_ref(), THUMBNAIL_FRAMES))
+            .collect();
+        Self {
+            roms,
+            titles,
+            entries,
+            thumbnails,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.roms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roms.is_empty()
+    }
+
+    // The first title shown on `rows` lines so that `selected` stays in the
+    // middle of the list
+    pub fn first_row(&self, selected: usize, rows: usize) -> usize {
+        let last = self.len().saturating_sub(rows);
+        selected.saturating_sub(rows / 2).min(last)
+    }
+}
+
+// The screen after running `frames` frames without any key, with the settings
+// of the database. A rom stopping on an error shows what it drew until then
+pub fn thumbnail(program: &Program, entry: Option<&Entry>, frames: u64) -> Screen {
+    let mut interpreter = Interpreter::new();
+    interpreter.seed_rng(0);
+    let program = match entry.and_then(|e| e.start_address) {
+        Some(address) => program.clone().at(address),
+        None => Ok(program.clone()),
+    };
+    if program.and_then(|p| interpreter.load_program(&p)).is_err() {
+        return Screen::new();
+    }
+    interpreter.tickrate = THUMBNAIL_TICKRATE;
+    if let Some(entry) = entry {
+        entry.apply(&mut interpreter);
+    }
+    for _ in 0..frames {
+        if interpreter.try_run_frame().is_err() {
+            break;
+        }
+    }
+    std::mem::take(&mut interpreter.cpu.display)
+}
@@ -0,0 +1,353 @@
+use crate::chip8::cfg::{Cfg, EdgeKind};
+use crate::chip8::decoder::decode;
+use crate::chip8::disassembler::Disassembler;
+use crate::chip8::instructions::Chip8;
+use crate::chip8::platform::Platform;
+use crate::chip8::program::Program;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+const MEMORY_SIZE: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        f.pad(name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lint {
+    UnknownOpcode,
+    SysCall(u16),
+    Quirk(&'static str),
+    Extension(Platform),
+    SelfModifying(u16),
+    ReadPastEnd(usize),
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::UnknownOpcode | Lint::ReadPastEnd(_) => Severity::Error,
+            Lint::SysCall(_) | Lint::SelfModifying(_) => Severity::Warning,
+            Lint::Quirk(_) | Lint::Extension(_) => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::UnknownOpcode => write!(f, "unknown opcode reachable from entry"),
+            Lint::SysCall(addr) => write!(f, "machine code call to {:03X}", addr),
+            Lint::Quirk(quirk) => write!(f, "behaviour depends on the {} quirk", quirk),
+            Lint::Extension(platform) => write!(f, "{} opcode", platform),
+            Lint::SelfModifying(addr) => write!(f, "writes into code at {:03X}", addr),
+            Lint::ReadPastEnd(end) => write!(f, "reads memory up to {:X}, past the end", end),
+        }
+    }
+}
+
+pub struct Finding {
+    pub addr: u16,
+    pub op: u16,
+    pub lint: Lint,
+}
+
+pub struct Report {
+    pub size: usize,
+    pub instructions: usize,
+    pub findings: Vec<Finding>,
+    pub platform: Platform,
+    // the quirks some reachable instruction depends on. clipping and
+    // display_wait are never listed : they depend on the sprite coordinates
+    // and on the timing, which a static pass does not know
+    pub quirks: BTreeSet<&'static str>,
+}
+
+impl Report {
+    pub fn has_errors(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|f| f.lint.severity() == Severity::Error)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} bytes, {} reachable instructions",
+            self.size, self.instructions
+        )?;
+        for finding in &self.findings {
+            let mut disassembler = Disassembler::new();
+            disassembler.step(finding.op);
+            writeln!(
+                f,
+                "{:<7} {:03X}  {:04X}  {:<16} {}",
+                finding.lint.severity(),
+                finding.addr,
+                finding.op,
+                disassembler.to_string().trim_end(),
+                finding.lint
+            )?;
+        }
+        writeln!(f, "guessed platform : {}", self.platform)?;
+        let quirks: Vec<&str> = self.quirks.iter().copied().collect();
+        if quirks.is_empty() {
+            writeln!(f, "quirks relied on : none")?;
+        } else {
+            writeln!(f, "quirks relied on : {}", quirks.join(", "))?;
+        }
+        writeln!(f, "quirk profile    : {}", self.platform.quirks())
+    }
+}
+
+// Walks one instruction at a time, tracking I when it is statically known
+struct Linter<'a> {
+    code: &'a BTreeSet<u16>,
+    addr: u16,
+    op: u16,
+    index: Option<u16>,
+    findings: Vec<Finding>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, lint: Lint) {
+        self.findings.push(Finding {
+            addr: self.addr,
+            op: self.op,
+            lint,
+        });
+    }
+
+    fn check_write(&mut self, len: u16) {
+        if let Some(index) = self.index {
+            if let Some(target) = (index..index.saturating_add(len)).find(|a| self.code.contains(a))
+            {
+                self.report(Lint::SelfModifying(target));
+            }
+        }
+    }
+
+    fn check_read(&mut self, len: usize) {
+        if let Some(index) = self.index {
+            let end = index as usize + len;
+            if end > MEMORY_SIZE {
+                self.report(Lint::ReadPastEnd(end));
+            }
+        }
+    }
+}
+
+pub fn lint(p: &Program) -> Report {
+    let cfg = Cfg::build(p);
+
+    // every byte covered by a reachable instruction
+    let mut code = BTreeSet::new();
+    for block in cfg.blocks.values() {
+        for (addr, _) in &block.instructions {
+            code.extend([*addr, addr.wrapping_add(1)]);
+        }
+    }
+
+    // propagate the value of I along the graph until nothing changes,
+    // the callee may change I so the return site starts unknown
+    let mut entry_index: BTreeMap<u16, Option<u16>> = BTreeMap::new();
+    entry_index.insert(cfg.entry, None);
+    let mut worklist = vec![cfg.entry];
+    while let Some(start) = worklist.pop() {
+        // an empty program has no block
+        let Some(block) = cfg.blocks.get(&start) else {
+            continue;
+        };
+        let mut linter = Linter {
+            code: &code,
+            addr: 0,
+            op: 0,
+            index: entry_index[&start],
+            findings: Vec::new(),
+        };
+        for (addr, op) in &block.instructions {
+            linter.addr = *addr;
+            linter.op = *op;
+            decode(*op, &mut linter);
+        }
+
+        let call = block.successors.iter().any(|e| e.kind == EdgeKind::Call);
+        for edge in &block.successors {
+            let index = if call && edge.kind == EdgeKind::Fallthrough {
+                None
+            } else {
+                linter.index
+            };
+            let merged = match entry_index.get(&edge.target) {
+                None => index,
+                Some(previous) if *previous == index => continue,
+                Some(_) => None,
+            };
+            if entry_index.insert(edge.target, merged) != Some(merged) {
+                worklist.push(edge.target);
+            }
+        }
+    }
+
+    let mut findings = Vec::new();
+    let mut instructions = 0;
+    for (start, block) in &cfg.blocks {
+        let mut linter = Linter {
+            code: &code,
+            addr: 0,
+            op: 0,
+            index: entry_index.get(start).copied().flatten(),
+            findings: Vec::new(),
+        };
+        for (addr, op) in &block.instructions {
+            linter.addr = *addr;
+            linter.op = *op;
+            decode(*op, &mut linter);
+        }
+        instructions += block.instructions.len();
+        findings.append(&mut linter.findings);
+    }
+
+    let platform = findings
+        .iter()
+        .filter_map(|f| match f.lint {
+            Lint::Extension(platform) => Some(platform),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(Platform::Chip8);
+    let quirks = findings
+        .iter()
+        .filter_map(|f| match f.lint {
+            Lint::Quirk(quirk) => Some(quirk),
+            _ => None,
+        })
+        .collect();
+
+    Report {
+        size: p.content.len(),
+        instructions,
+        findings,
+        platform,
+        quirks,
+    }
+}
+
+impl Chip8 for Linter<'_> {
+    // 0
+    fn cls(&mut self) {}
+    fn ret(&mut self) {}
+    fn sys_addr(&mut self, addr: u16) {
+        self.report(Lint::SysCall(addr));
+    }
+    // 1
+    fn jp_addr(&mut self, _addr: u16) {}
+    // 2
+    fn call_addr(&mut self, _addr: u16) {}
+    // 3
+    fn se_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // 4
+    fn sne_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // 5
+    fn se_vx_vy(&mut self, _x: u8, _y: u8) {
+        // 5XY2 and 5XY3 save and load registers on XO-CHIP
+        if let Some(platform) = Platform::of_opcode(self.op) {
+            self.report(Lint::Extension(platform));
+        }
+    }
+    // 6
+    fn ld_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // 7
+    fn add_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // 8
+    fn ld_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn or_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.report(Lint::Quirk("vf_reset"));
+    }
+    fn and_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.report(Lint::Quirk("vf_reset"));
+    }
+    fn xor_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.report(Lint::Quirk("vf_reset"));
+    }
+    fn add_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn sub_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn shr_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.report(Lint::Quirk("shifting"));
+    }
+    fn subn_vx_vy(&mut self, _x: u8, _y: u8) {}
+    fn shl_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.report(Lint::Quirk("shifting"));
+    }
+    fn sne_vx_vy(&mut self, _x: u8, _y: u8) {}
+    // A
+    fn ld_i_addr(&mut self, addr: u16) {
+        self.index = Some(addr);
+    }
+    // B
+    fn jp_v0_addr(&mut self, _addr: u16) {
+        self.report(Lint::Quirk("jumping"));
+    }
+    // C
+    fn rnd_vx_byte(&mut self, _x: u8, _byte: u8) {}
+    // D
+    fn drw_vx_vy_nibble(&mut self, _x: u8, _y: u8, nibble: u16) {
+        if nibble == 0 {
+            // 16x16 sprite of two bytes per row
+            self.report(Lint::Extension(Platform::SuperChip));
+            self.check_read(32);
+        } else {
+            self.check_read(nibble as usize);
+        }
+    }
+    // E
+    fn skp_vx(&mut self, _x: u8) {}
+    fn sknp_vx(&mut self, _x: u8) {}
+    // F
+    fn ld_vx_dt(&mut self, _x: u8) {}
+    fn ld_vx_k(&mut self, _x: u8) {}
+    fn ld_dt_vx(&mut self, _x: u8) {}
+    fn ld_st_vx(&mut self, _x: u8) {}
+    fn add_i_vx(&mut self, _x: u8) {
+        self.index = None;
+    }
+    fn ld_f_vx(&mut self, _x: u8) {
+        self.index = None;
+    }
+    fn ld_b_vx(&mut self, _x: u8) {
+        self.check_write(3);
+    }
+    fn ld_i_vx(&mut self, x: u8) {
+        self.report(Lint::Quirk("memory"));
+        self.check_write(x as u16 + 1);
+        self.index = None;
+    }
+    fn ld_vx_i(&mut self, x: u8) {
+        self.report(Lint::Quirk("memory"));
+        self.check_read(x as usize + 1);
+        self.index = None;
+    }
+
+    fn unknown(&mut self, op: u16) {
+        match Platform::of_opcode(op) {
+            Some(platform) => self.report(Lint::Extension(platform)),
+            None => self.report(Lint::UnknownOpcode),
+        }
+    }
+}
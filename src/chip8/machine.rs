@@ -0,0 +1,257 @@
+use crate::chip8::interpreter::{Error, Interpreter};
+use crate::screen::Screen;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+// The keys bound to the keypad 0 to F by the frontends, the 4x4 block on the
+// left of the keyboard laid out as the keypad of the VIP :
+// 1 2 3 C / 4 5 6 D / 7 8 9 E / A 0 B F
+pub const AZERTY: [char; 16] = [
+    'x', '1', '2', '3', 'a', 'z', 'e', 'q', 's', 'd', 'w', 'c', '4', 'r', 'f', 'v',
+];
+pub const QWERTY: [char; 16] = [
+    'x', '1', '2', '3', 'q', 'w', 'e', 'a', 's', 'd', 'z', 'c', '4', 'r', 'f', 'v',
+];
+
+// Shows the frames
+pub trait VideoSink {
+    fn present(&mut self, screen: &Screen);
+
+    // Called when the program starts or stops waiting for a key with FX0A
+    fn waiting_for_key(&mut self, _waiting: bool) {}
+}
+
+// Why the machine stopped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exit {
+    Quit,
+    // the next or previous rom of an archive or a directory
+    Next,
+    Previous,
+    // back to the launcher
+    Menu,
+}
+
+// Gives the keys held, polled once per frame
+pub trait InputSource {
+    // Updates `keys`, false when the user wants to stop
+    fn poll(&mut self, keys: &mut [bool; 16]) -> bool;
+
+    // What the user asked for when poll returned false
+    fn exit(&mut self) -> Exit {
+        Exit::Quit
+    }
+}
+
+// Plays the buzzer
+pub trait AudioSink {
+    // Called every frame, the buzzer sounds while the sound timer is not 0
+    fn set_tone(&mut self, on: bool);
+}
+
+// Paces the frames
+pub trait Clock {
+    // Returns when the next frame is due
+    fn wait_frame(&mut self);
+}
+
+// Everything the machine needs to run, implemented by any type providing the four
+pub trait Frontend: VideoSink + InputSource + AudioSink + Clock {}
+
+impl<T: VideoSink + InputSource + AudioSink + Clock> Frontend for T {}
+
+// A frontend made of separate parts, to mix for example a terminal display
+// with the silent audio of ()
+pub struct Parts<V, I, A, C> {
+    pub video: V,
+    pub input: I,
+    pub audio: A,
+    pub clock: C,
+}
+
+impl<V: VideoSink, I, A, C> VideoSink for Parts<V, I, A, C> {
+    fn present(&mut self, screen: &Screen) {
+        self.video.present(screen)
+    }
+
+    fn waiting_for_key(&mut self, waiting: bool) {
+        self.video.waiting_for_key(waiting)
+    }
+}
+
+impl<V, I: InputSource, A, C> InputSource for Parts<V, I, A, C> {
+    fn poll(&mut self, keys: &mut [bool; 16]) -> bool {
+        self.input.poll(keys)
+    }
+
+    fn exit(&mut self) -> Exit {
+        self.input.exit()
+    }
+}
+
+impl<V, I, A: AudioSink, C> AudioSink for Parts<V, I, A, C> {
+    fn set_tone(&mut self, on: bool) {
+        self.audio.set_tone(on)
+    }
+}
+
+impl<V, I, A, C: Clock> Clock for Parts<V, I, A, C> {
+    fn wait_frame(&mut self) {
+        self.clock.wait_frame()
+    }
+}
+
+// Nothing to show, no key held, silence and no waiting
+impl VideoSink for () {
+    fn present(&mut self, _screen: &Screen) {}
+}
+
+impl InputSource for () {
+    fn poll(&mut self, _keys: &mut [bool; 16]) -> bool {
+        true
+    }
+}
+
+impl AudioSink for () {
+    fn set_tone(&mut self, _on: bool) {}
+}
+
+impl Clock for () {
+    fn wait_frame(&mut self) {}
+}
+
+// Sleeps until the next frame at a fixed rate, a late frame does not make
+// the following ones faster
+#[cfg(feature = "std")]
+pub struct FixedRate {
+    period: Duration,
+    next: Option<Instant>,
+}
+
+#[cfg(feature = "std")]
+impl FixedRate {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            period: Duration::from_secs(1) / fps.max(1),
+            next: None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for FixedRate {
+    fn wait_frame(&mut self) {
+        let now = Instant::now();
+        let next = self.next.unwrap_or(now) + self.period;
+        if next > now {
+            std::thread::sleep(next - now);
+            self.next = Some(next);
+        } else {
+            self.next = Some(now);
+        }
+    }
+}
+
+// Runs as fast as possible for a number of frames with scripted keys, for tests
+// and benchmarks
+pub struct Headless {
+    pub frames: u64,
+    // (frame, key, pressed)
+    pub keys: Vec<(u64, usize, bool)>,
+    // frames the buzzer sounded
+    pub beeps: u64,
+    frame: u64,
+}
+
+impl Headless {
+    pub fn new(frames: u64) -> Self {
+        Self {
+            frames,
+            keys: Vec::new(),
+            beeps: 0,
+            frame: 0,
+        }
+    }
+}
+
+impl VideoSink for Headless {
+    fn present(&mut self, _screen: &Screen) {}
+}
+
+impl InputSource for Headless {
+    fn poll(&mut self, keys: &mut [bool; 16]) -> bool {
+        for &(frame, key, pressed) in &self.keys {
+            if frame == self.frame {
+                keys[key] = pressed;
+            }
+        }
+        self.frame += 1;
+        self.frame <= self.frames
+    }
+}
+
+impl AudioSink for Headless {
+    fn set_tone(&mut self, on: bool) {
+        self.beeps += on as u64;
+    }
+}
+
+impl Clock for Headless {
+    fn wait_frame(&mut self) {}
+}
+
+// The loop shared by every frontend : input, a frame of execution, then
+// video, audio and pacing
+pub struct Machine<F> {
+    pub interpreter: Interpreter,
+    pub frontend: F,
+    waiting_for_key: bool,
+}
+
+impl<F: Frontend> Machine<F> {
+    pub fn new(interpreter: Interpreter, frontend: F) -> Self {
+        Self {
+            interpreter,
+            frontend,
+            waiting_for_key: false,
+        }
+    }
+
+    // Runs one frame, false when the frontend quits
+    pub fn frame(&mut self) -> bool {
+        match self.try_frame() {
+            Ok(running) => running,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    // Same as frame but returns the error of an instruction that cannot be
+    // executed instead of panicking
+    pub fn try_frame(&mut self) -> Result<bool, Error> {
+        if !self.frontend.poll(&mut self.interpreter.cpu.keys) {
+            return Ok(false);
+        }
+        self.interpreter.try_run_frame()?;
+
+        let waiting = self.interpreter.waiting_for_key();
+        if waiting != self.waiting_for_key {
+            self.waiting_for_key = waiting;
+            self.frontend.waiting_for_key(waiting);
+        }
+        self.frontend.present(&self.interpreter.cpu.display);
+        self.frontend.set_tone(self.interpreter.cpu.sound_timer > 0);
+        self.frontend.wait_frame();
+        Ok(true)
+    }
+
+    pub fn run(&mut self) -> Exit {
+        while self.frame() {}
+        self.frontend.exit()
+    }
+
+    pub fn try_run(&mut self) -> Result<Exit, Error> {
+        while self.try_frame()? {}
+        Ok(self.frontend.exit())
+    }
+}
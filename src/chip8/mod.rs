@@ -1,7 +1,32 @@
 pub mod disassembler;
+pub mod assembler;
+#[cfg(feature = "std")]
+pub mod archive;
 pub mod cpu;
 pub mod program;
 pub mod interpreter;
 pub mod instructions;
 pub mod decoder;
-pub mod font;
\ No newline at end of file
+pub mod font;
+pub mod cdp1802;
+#[cfg(feature = "std")]
+pub mod cfg;
+#[cfg(feature = "terminal")]
+pub mod dashboard;
+#[cfg(feature = "std")]
+pub mod database;
+pub mod debugger;
+#[cfg(feature = "std")]
+pub mod launcher;
+#[cfg(feature = "std")]
+pub mod lint;
+pub mod machine;
+pub mod platform;
+#[cfg(feature = "std")]
+pub mod recompiler;
+pub mod sys;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+pub mod timing;
+#[cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+pub mod jit;
\ No newline at end of file
@@ -0,0 +1,152 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Platform {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Platform {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Chip8 => "chip-8",
+            Platform::SuperChip => "schip",
+            Platform::XoChip => "xo-chip",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Platform> {
+        match name {
+            "chip-8" | "chip8" | "vip" => Some(Platform::Chip8),
+            "schip" | "superchip" => Some(Platform::SuperChip),
+            "xo-chip" | "xochip" => Some(Platform::XoChip),
+            _ => None,
+        }
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Platform::Chip8 => Quirks {
+                vf_reset: true,
+                memory: true,
+                display_wait: true,
+                clipping: true,
+                shifting: false,
+                jumping: false,
+            },
+            Platform::SuperChip => Quirks {
+                vf_reset: false,
+                memory: false,
+                display_wait: false,
+                clipping: true,
+                shifting: true,
+                jumping: true,
+            },
+            Platform::XoChip => Quirks {
+                vf_reset: false,
+                memory: true,
+                display_wait: false,
+                clipping: false,
+                shifting: false,
+                jumping: false,
+            },
+        }
+    }
+
+    // The platform introducing an opcode the original CHIP-8 does not have
+    pub fn of_opcode(op: u16) -> Option<Platform> {
+        let x = (op & 0x0F00) >> 8;
+        match op & 0xF000 {
+            0x0000 => match op {
+                0x00C1..=0x00CF | 0x00FB..=0x00FF => Some(Platform::SuperChip),
+                0x00D1..=0x00DF => Some(Platform::XoChip),
+                _ => None,
+            },
+            0x5000 => match op & 0x000F {
+                0x0002 | 0x0003 => Some(Platform::XoChip),
+                _ => None,
+            },
+            0xD000 if op & 0x000F == 0 => Some(Platform::SuperChip),
+            0xF000 => match op & 0x00FF {
+                0x0030 | 0x0075 | 0x0085 => Some(Platform::SuperChip),
+                0x0000 if x == 0 => Some(Platform::XoChip),
+                0x0001 | 0x003A => Some(Platform::XoChip),
+                0x0002 if x == 0 => Some(Platform::XoChip),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// Behaviours that differ between the CHIP-8 implementations, named after the Timendus quirks test
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY1, 8XY2 and 8XY3 reset VF to 0
+    pub vf_reset: bool,
+    // FX55 and FX65 increment I
+    pub memory: bool,
+    // DXYN waits for the vertical blank
+    pub display_wait: bool,
+    // sprites are clipped at the screen edges instead of wrapping
+    pub clipping: bool,
+    // 8XY6 and 8XYE shift VX in place and ignore VY
+    pub shifting: bool,
+    // BNNN jumps to XNN + VX instead of NNN + V0
+    pub jumping: bool,
+}
+
+impl Quirks {
+    // Changes the quirks named like in Display, "vf_reset=off,shifting=on", a
+    // name alone turns the quirk on
+    pub fn update(&mut self, flags: &str) -> Result<(), String> {
+        for flag in flags.split([',', ' ']).filter(|f| !f.is_empty()) {
+            let (name, value) = flag.split_once('=').unwrap_or((flag, "on"));
+            let on = match value {
+                "on" | "true" | "1" => true,
+                "off" | "false" | "0" => false,
+                _ => return Err(format!("{} : expected on or off", flag)),
+            };
+            let quirk = match name {
+                "vf_reset" => &mut self.vf_reset,
+                "memory" => &mut self.memory,
+                "display_wait" => &mut self.display_wait,
+                "clipping" => &mut self.clipping,
+                "shifting" => &mut self.shifting,
+                "jumping" => &mut self.jumping,
+                _ => return Err(format!("unknown quirk {}", name)),
+            };
+            *quirk = on;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Quirks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flags = [
+            ("vf_reset", self.vf_reset),
+            ("memory", self.memory),
+            ("display_wait", self.display_wait),
+            ("clipping", self.clipping),
+            ("shifting", self.shifting),
+            ("jumping", self.jumping),
+        ];
+        for (i, (name, on)) in flags.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}={}", name, if *on { "on" } else { "off" })?;
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,150 @@
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
+use crate::chip8::platform::Platform;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::path::Path;
 
+// Where programs start on the VIP, the ETI-660 loads them at 0x600
+pub const LOAD_ADDRESS: u16 = 0x200;
+// The memory of the emulator
+pub const MEMORY_SIZE: usize = 4096;
+
+#[derive(Clone)]
 pub struct Program {
     pub content: Vec<u8>,
+    pub address: u16,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    // does not fit between the load address and the end of memory
+    TooLarge { size: usize, max: usize },
+    BadAddress(u16),
+    // an archive or a directory without any file fitting in memory
+    NoRom,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::TooLarge { size, max } => {
+                write!(f, "program of {} bytes, at most {} fit", size, max)
+            }
+            LoadError::BadAddress(addr) => write!(f, "load address {:X} out of memory", addr),
+            LoadError::NoRom => write!(f, "no rom found"),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        LoadError::Io(e)
+    }
 }
 
-impl From<String> for Program {
-    fn from(filename: String) -> Self {
-        let file = File::open(filename).unwrap();
-        let mut buf_reader = BufReader::new(file);
+// The bytes available from `address` : the VIP keeps its variables and display
+// from 0xEA0, SCHIP has the whole 4K and XO-CHIP 64K
+pub fn max_size(platform: Platform, address: u16) -> usize {
+    let end = match platform {
+        Platform::Chip8 => 0xEA0,
+        Platform::SuperChip => 0x1000,
+        Platform::XoChip => 0x10000,
+    };
+    end - (address as usize).min(end)
+}
+
+impl Program {
+    // A program loaded at 0x200, an error when it does not fit in memory
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        let program = Self::from(bytes.to_vec());
+        program.fits(MEMORY_SIZE)?;
+        Ok(program)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, LoadError> {
+        // one byte more than memory to tell a full memory from a larger file
         let mut content = Vec::new();
-        buf_reader.read_to_end(&mut content).unwrap();
+        reader
+            .by_ref()
+            .take(MEMORY_SIZE as u64 + 1)
+            .read_to_end(&mut content)?;
+        Self::from_bytes(&content)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let file = std::fs::File::open(path)?;
+        // the reader stops past the memory, the real size is in the error
+        let size = file.metadata()?.len() as usize;
+        if size > MEMORY_SIZE {
+            let max = MEMORY_SIZE - LOAD_ADDRESS as usize;
+            return Err(LoadError::TooLarge { size, max });
+        }
+        Self::from_reader(file)
+    }
+
+    // Loads the program at another address, 0x600 for the ETI-660
+    pub fn at(mut self, address: u16) -> Result<Self, LoadError> {
+        if address as usize >= MEMORY_SIZE {
+            return Err(LoadError::BadAddress(address));
+        }
+        self.address = address;
+        self.fits(MEMORY_SIZE)?;
+        Ok(self)
+    }
+
+    // Whether the program fits in the memory of the platform
+    pub fn check(&self, platform: Platform) -> Result<(), LoadError> {
+        self.fits(MEMORY_SIZE.min(self.address as usize + max_size(platform, self.address)))
+    }
+
+    fn fits(&self, end: usize) -> Result<(), LoadError> {
+        let max = end.saturating_sub(self.address as usize);
+        if self.content.len() > max {
+            return Err(LoadError::TooLarge {
+                size: self.content.len(),
+                max,
+            });
+        }
+        Ok(())
+    }
+
+    // Identify a rom, the SHA-1 is the key of the CHIP-8 database
+    pub fn sha1(&self) -> [u8; 20] {
+        sha1_smol::Sha1::from(&self.content).digest().bytes()
+    }
+
+    pub fn sha1_hex(&self) -> String {
+        use core::fmt::Write;
+        let mut hex = String::new();
+        for byte in self.sha1() {
+            write!(hex, "{:02x}", byte).unwrap();
+        }
+        hex
+    }
+
+    pub fn crc32(&self) -> u32 {
+        crc32fast::hash(&self.content)
+    }
+}
 
+// Loaded at 0x200 without any check, see from_bytes
+impl From<Vec<u8>> for Program {
+    fn from(content: Vec<u8>) -> Self {
         Self {
-            content: content
+            content,
+            address: LOAD_ADDRESS,
         }
     }
 }
@@ -0,0 +1,420 @@
+use crate::chip8::cfg::{BasicBlock, Cfg, Flow};
+use crate::chip8::instructions::Instruction;
+use crate::chip8::program::Program;
+use std::fmt::Write;
+use std::path::Path;
+
+// Translates every basic block recovered from the program into a Rust
+// function, the generated `step` dispatches on the program counter and
+// falls back to the interpreter for unknown addresses and modified code
+pub fn recompile(p: &Program, name: &str) -> String {
+    let cfg = Cfg::build(p);
+    let segments: Vec<Segment> = cfg.blocks.values().flat_map(segments).collect();
+    let mut rs = String::new();
+
+    writeln!(rs, "// Recompiled from {} by `chip8 recompile`", name).unwrap();
+    writeln!(rs, "use chip8::chip8::instructions::Chip8;").unwrap();
+    writeln!(rs, "use chip8::chip8::interpreter::Interpreter;").unwrap();
+    writeln!(rs, "use chip8::chip8::program::Program;").unwrap();
+    writeln!(rs).unwrap();
+
+    write!(rs, "const ROM: [u8; {}] = [", p.content.len()).unwrap();
+    for (i, byte) in p.content.iter().enumerate() {
+        if i % 16 == 0 {
+            write!(rs, "\n   ").unwrap();
+        }
+        write!(rs, " 0x{:02X},", byte).unwrap();
+    }
+    writeln!(rs, "\n];").unwrap();
+    writeln!(rs).unwrap();
+
+    writeln!(rs, "pub fn load(i: &mut Interpreter) {{").unwrap();
+    writeln!(rs, "    i.load_program(&Program::from(ROM.to_vec())).unwrap();").unwrap();
+    writeln!(rs, "}}").unwrap();
+    writeln!(rs).unwrap();
+
+    // the dispatcher also serves the indirect jumps
+    writeln!(
+        rs,
+        "// Runs a block from the program counter when it fits in the budget, returns the number of"
+    )
+    .unwrap();
+    writeln!(rs, "// instructions executed").unwrap();
+    writeln!(rs, "pub fn step(i: &mut Interpreter, budget: u32) -> u32 {{").unwrap();
+    writeln!(rs, "    let pc = i.cpu.program_counter;").unwrap();
+    writeln!(rs, "    let (start, end, len, block): (u16, u16, u32, fn(&mut Interpreter) -> u16) = match pc {{").unwrap();
+    for segment in &segments {
+        writeln!(
+            rs,
+            "        0x{:03X} => (0x{:03X}, 0x{:03X}, {}, block_{:03x}),",
+            segment.start,
+            segment.start,
+            segment.end(),
+            segment.instructions.len(),
+            segment.start
+        )
+        .unwrap();
+    }
+    writeln!(rs, "        _ => {{").unwrap();
+    writeln!(rs, "            i.step();").unwrap();
+    writeln!(rs, "            return 1;").unwrap();
+    writeln!(rs, "        }}").unwrap();
+    writeln!(rs, "    }};").unwrap();
+    writeln!(
+        rs,
+        "    // the frame ends inside the block, or the code was modified since it was recompiled"
+    )
+    .unwrap();
+    writeln!(
+        rs,
+        "    if len > budget || i.cpu.memory[start as usize..end as usize] != ROM[(start - 0x200) as usize..(end - 0x200) as usize] {{"
+    )
+    .unwrap();
+    writeln!(rs, "        i.step();").unwrap();
+    writeln!(rs, "        return 1;").unwrap();
+    writeln!(rs, "    }}").unwrap();
+    writeln!(rs, "    i.cpu.program_counter = block(i);").unwrap();
+    writeln!(rs, "    len").unwrap();
+    writeln!(rs, "}}").unwrap();
+    writeln!(rs).unwrap();
+
+    // the same frame as Interpreter::run_frame, a sprite always starts a block
+    writeln!(
+        rs,
+        "// Runs the instructions of a frame then ticks the timers, like Interpreter::run_frame"
+    )
+    .unwrap();
+    writeln!(rs, "pub fn run_frame(i: &mut Interpreter) {{").unwrap();
+    writeln!(rs, "    // the VIP clock counts the cycles of every instruction").unwrap();
+    writeln!(rs, "    if i.clock.is_some() {{").unwrap();
+    writeln!(rs, "        i.run_frame();").unwrap();
+    writeln!(rs, "        return;").unwrap();
+    writeln!(rs, "    }}").unwrap();
+    writeln!(rs, "    let mut executed = 0;").unwrap();
+    writeln!(rs, "    while executed < i.tickrate {{").unwrap();
+    writeln!(rs, "        if i.waits_display(executed == 0) {{").unwrap();
+    writeln!(rs, "            break;").unwrap();
+    writeln!(rs, "        }}").unwrap();
+    writeln!(rs, "        executed += step(i, i.tickrate - executed);").unwrap();
+    writeln!(rs, "    }}").unwrap();
+    writeln!(rs, "    i.tick_timers();").unwrap();
+    writeln!(rs, "}}").unwrap();
+
+    for segment in &segments {
+        writeln!(rs).unwrap();
+        write_block(&mut rs, segment);
+    }
+
+    rs
+}
+
+// A main function running the recompiled program in a SDL window
+pub fn main_sdl(name: &str) -> String {
+    format!(
+        r#"
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use std::time::Duration;
+
+// the keypad 0 to F on an AZERTY keyboard, 1 2 3 C / 4 5 6 D / 7 8 9 E / A 0 B F
+const KEYS: [Keycode; 16] = [
+    Keycode::X, Keycode::Num1, Keycode::Num2, Keycode::Num3,
+    Keycode::A, Keycode::Z, Keycode::E, Keycode::Q,
+    Keycode::S, Keycode::D, Keycode::W, Keycode::C,
+    Keycode::Num4, Keycode::R, Keycode::F, Keycode::V,
+];
+
+fn main() {{
+    let mut interpreter = Interpreter::new();
+    load(&mut interpreter);
+
+    let sdl_context = sdl2::init().unwrap();
+    let video_subsystem = sdl_context.video().unwrap();
+    let window = video_subsystem
+        .window("{}", 640, 320)
+        .position_centered()
+        .build()
+        .unwrap();
+    let mut canvas = window.into_canvas().build().unwrap();
+    let mut event_pump = sdl_context.event_pump().unwrap();
+
+    'running: loop {{
+        for event in event_pump.poll_iter() {{
+            match event {{
+                Event::Quit {{ .. }}
+                | Event::KeyDown {{
+                    keycode: Some(Keycode::Escape),
+                    ..
+                }} => break 'running,
+                Event::KeyDown {{
+                    keycode: Some(k), ..
+                }} => {{
+                    if let Some(key) = KEYS.iter().position(|c| *c == k) {{
+                        interpreter.cpu.keys[key] = true;
+                    }}
+                }}
+                Event::KeyUp {{
+                    keycode: Some(k), ..
+                }} => {{
+                    if let Some(key) = KEYS.iter().position(|c| *c == k) {{
+                        interpreter.cpu.keys[key] = false;
+                    }}
+                }}
+                _ => {{}}
+            }}
+        }}
+
+        run_frame(&mut interpreter);
+
+        for y in 0..32 {{
+            for x in 0..64 {{
+                if interpreter.cpu.display[(y, x)] {{
+                    canvas.set_draw_color(Color::BLACK);
+                }} else {{
+                    canvas.set_draw_color(Color::WHITE);
+                }}
+                canvas
+                    .fill_rect(sdl2::rect::Rect::new((x * 10) as i32, (y * 10) as i32, 10, 10))
+                    .unwrap();
+            }}
+        }}
+        canvas.present();
+
+        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
+    }}
+}}
+"#,
+        name
+    )
+}
+
+// Cargo.toml of a standalone crate depending on this library, from a local
+// checkout when a path is given and from its version otherwise
+pub fn manifest(name: &str, chip8_path: Option<&Path>) -> String {
+    let chip8 = match chip8_path {
+        Some(path) => format!("{{ path = {:?} }}", path.display().to_string()),
+        None => format!("\"{}\"", env!("CARGO_PKG_VERSION")),
+    };
+    format!(
+        r#"[package]
+name = "{}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+chip8 = {}
+sdl2 = "0.35.2"
+"#,
+        name, chip8
+    )
+}
+
+// The part of a basic block compiled to one function, it also ends after the
+// instructions left to the interpreter and the ones writing into memory so
+// that the dispatcher checks the code after them again, and before a sprite
+// so that the frame can wait for the display
+struct Segment<'a> {
+    start: u16,
+    // (address, opcode) pairs
+    instructions: &'a [(u16, u16)],
+    // continues at the next address after the last instruction
+    falls_through: bool,
+}
+
+impl Segment<'_> {
+    fn end(&self) -> u16 {
+        self.start + 2 * self.instructions.len() as u16
+    }
+}
+
+fn ends_segment(op: u16) -> bool {
+    match Instruction::decode(op) {
+        Ok(instruction) => {
+            changes_flow(instruction)
+                || matches!(
+                    instruction,
+                    Instruction::SysAddr { .. }
+                        | Instruction::LdBVx { .. }
+                        | Instruction::LdIVx { .. }
+                )
+        }
+        Err(_) => true,
+    }
+}
+
+fn draws(op: u16) -> bool {
+    op & 0xF000 == 0xD000
+}
+
+fn segments(block: &BasicBlock) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = &block.instructions[..];
+    while !rest.is_empty() {
+        let len = (1..rest.len())
+            .find(|&n| ends_segment(rest[n - 1].1) || draws(rest[n].1))
+            .unwrap_or(rest.len());
+        let (instructions, after) = rest.split_at(len);
+        segments.push(Segment {
+            start: instructions[0].0,
+            instructions,
+            falls_through: !after.is_empty() || block.terminator == Flow::Next,
+        });
+        rest = after;
+    }
+    segments
+}
+
+fn write_block(rs: &mut String, block: &Segment) {
+    // blocks made of a single jump do not use the interpreter
+    writeln!(rs, "#[allow(unused_variables)]").unwrap();
+    writeln!(rs, "fn block_{:03x}(i: &mut Interpreter) -> u16 {{", block.start).unwrap();
+    for (addr, op) in block.instructions {
+        let next = addr.wrapping_add(2);
+        let instruction = match Instruction::decode(*op) {
+            Ok(instruction) => instruction,
+            Err(_) => {
+                // left to the interpreter, the rest of the block runs on the next step
+                writeln!(rs, "    // {:03X}: {:04X}", addr, op).unwrap();
+                writeln!(rs, "    i.cpu.program_counter = 0x{:03X};", addr).unwrap();
+                writeln!(rs, "    i.step();").unwrap();
+                writeln!(rs, "    i.cpu.program_counter").unwrap();
+                writeln!(rs, "}}").unwrap();
+                return;
+            }
+        };
+
+        writeln!(rs, "    // {:03X}: {}", addr, instruction).unwrap();
+        match instruction {
+            Instruction::JpAddr { addr } => writeln!(rs, "    0x{:03X}", addr).unwrap(),
+            Instruction::SeVxByte { x, byte } => write_skip(
+                rs,
+                &format!("i.cpu.registers[{}] == 0x{:02X}", x, byte),
+                next,
+            ),
+            Instruction::SneVxByte { x, byte } => write_skip(
+                rs,
+                &format!("i.cpu.registers[{}] != 0x{:02X}", x, byte),
+                next,
+            ),
+            Instruction::SeVxVy { x, y } => write_skip(
+                rs,
+                &format!("i.cpu.registers[{}] == i.cpu.registers[{}]", x, y),
+                next,
+            ),
+            Instruction::SneVxVy { x, y } => write_skip(
+                rs,
+                &format!("i.cpu.registers[{}] != i.cpu.registers[{}]", x, y),
+                next,
+            ),
+            Instruction::LdVxByte { x, byte } => {
+                writeln!(rs, "    i.cpu.registers[{}] = 0x{:02X};", x, byte).unwrap()
+            }
+            Instruction::AddVxByte { x, byte } => writeln!(
+                rs,
+                "    i.cpu.registers[{}] = i.cpu.registers[{}].wrapping_add(0x{:02X});",
+                x, x, byte
+            )
+            .unwrap(),
+            Instruction::LdVxVy { x, y } => writeln!(
+                rs,
+                "    i.cpu.registers[{}] = i.cpu.registers[{}];",
+                x, y
+            )
+            .unwrap(),
+            Instruction::LdIAddr { addr } => {
+                writeln!(rs, "    i.cpu.index = 0x{:03X};", addr).unwrap()
+            }
+            Instruction::LdVxDt { x } => {
+                writeln!(rs, "    i.cpu.registers[{}] = i.cpu.delay_timer;", x).unwrap()
+            }
+            Instruction::LdDtVx { x } => {
+                writeln!(rs, "    i.cpu.delay_timer = i.cpu.registers[{}];", x).unwrap()
+            }
+            Instruction::LdStVx { x } => {
+                writeln!(rs, "    i.cpu.sound_timer = i.cpu.registers[{}];", x).unwrap()
+            }
+            _ => {
+                // the interpreter sees the program counter it would have after the fetch
+                writeln!(rs, "    i.cpu.program_counter = 0x{:03X};", next).unwrap();
+                writeln!(rs, "    {};", trait_call(instruction)).unwrap();
+                if changes_flow(instruction) {
+                    writeln!(rs, "    i.cpu.program_counter").unwrap();
+                    writeln!(rs, "}}").unwrap();
+                    return;
+                }
+            }
+        }
+    }
+
+    // the block falls through to the next one
+    if block.falls_through {
+        writeln!(rs, "    0x{:03X}", block.end()).unwrap();
+    }
+    writeln!(rs, "}}").unwrap();
+}
+
+fn write_skip(rs: &mut String, condition: &str, next: u16) {
+    writeln!(
+        rs,
+        "    if {} {{ 0x{:03X} }} else {{ 0x{:03X} }}",
+        condition,
+        next.wrapping_add(2),
+        next
+    )
+    .unwrap();
+}
+
+fn changes_flow(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Ret
+            | Instruction::CallAddr { .. }
+            | Instruction::JpV0Addr { .. }
+            | Instruction::SkpVx { .. }
+            | Instruction::SknpVx { .. }
+            | Instruction::LdVxK { .. }
+    )
+}
+
+fn trait_call(instruction: Instruction) -> String {
+    match instruction {
+        Instruction::Cls => "i.cls()".to_string(),
+        Instruction::Ret => "i.ret()".to_string(),
+        Instruction::SysAddr { addr } => format!("i.sys_addr(0x{:03X})", addr),
+        Instruction::JpAddr { addr } => format!("i.jp_addr(0x{:03X})", addr),
+        Instruction::CallAddr { addr } => format!("i.call_addr(0x{:03X})", addr),
+        Instruction::SeVxByte { x, byte } => format!("i.se_vx_byte({}, 0x{:02X})", x, byte),
+        Instruction::SneVxByte { x, byte } => format!("i.sne_vx_byte({}, 0x{:02X})", x, byte),
+        Instruction::SeVxVy { x, y } => format!("i.se_vx_vy({}, {})", x, y),
+        Instruction::LdVxByte { x, byte } => format!("i.ld_vx_byte({}, 0x{:02X})", x, byte),
+        Instruction::AddVxByte { x, byte } => format!("i.add_vx_byte({}, 0x{:02X})", x, byte),
+        Instruction::LdVxVy { x, y } => format!("i.ld_vx_vy({}, {})", x, y),
+        Instruction::OrVxVy { x, y } => format!("i.or_vx_vy({}, {})", x, y),
+        Instruction::AndVxVy { x, y } => format!("i.and_vx_vy({}, {})", x, y),
+        Instruction::XorVxVy { x, y } => format!("i.xor_vx_vy({}, {})", x, y),
+        Instruction::AddVxVy { x, y } => format!("i.add_vx_vy({}, {})", x, y),
+        Instruction::SubVxVy { x, y } => format!("i.sub_vx_vy({}, {})", x, y),
+        Instruction::ShrVxVy { x, y } => format!("i.shr_vx_vy({}, {})", x, y),
+        Instruction::SubnVxVy { x, y } => format!("i.subn_vx_vy({}, {})", x, y),
+        Instruction::ShlVxVy { x, y } => format!("i.shl_vx_vy({}, {})", x, y),
+        Instruction::SneVxVy { x, y } => format!("i.sne_vx_vy({}, {})", x, y),
+        Instruction::LdIAddr { addr } => format!("i.ld_i_addr(0x{:03X})", addr),
+        Instruction::JpV0Addr { addr } => format!("i.jp_v0_addr(0x{:03X})", addr),
+        Instruction::RndVxByte { x, byte } => format!("i.rnd_vx_byte({}, 0x{:02X})", x, byte),
+        Instruction::DrwVxVyNibble { x, y, nibble } => {
+            format!("i.drw_vx_vy_nibble({}, {}, {})", x, y, nibble)
+        }
+        Instruction::SkpVx { x } => format!("i.skp_vx({})", x),
+        Instruction::SknpVx { x } => format!("i.sknp_vx({})", x),
+        Instruction::LdVxDt { x } => format!("i.ld_vx_dt({})", x),
+        Instruction::LdVxK { x } => format!("i.ld_vx_k({})", x),
+        Instruction::LdDtVx { x } => format!("i.ld_dt_vx({})", x),
+        Instruction::LdStVx { x } => format!("i.ld_st_vx({})", x),
+        Instruction::AddIVx { x } => format!("i.add_i_vx({})", x),
+        Instruction::LdFVx { x } => format!("i.ld_f_vx({})", x),
+        Instruction::LdBVx { x } => format!("i.ld_b_vx({})", x),
+        Instruction::LdIVx { x } => format!("i.ld_i_vx({})", x),
+        Instruction::LdVxI { x } => format!("i.ld_vx_i({})", x),
+    }
+}
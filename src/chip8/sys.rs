@@ -0,0 +1,119 @@
+use crate::chip8::cpu::Cpu;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// A machine code routine emulated natively
+pub type Routine = fn(&mut Cpu);
+
+// What SYS NNN does, VIP programs call 1802 machine code routines with it
+pub enum SysPolicy {
+    // continue with the next instruction
+    Ignore,
+    // panic in `step`, an error from `try_step`
+    Error,
+    // run the routine registered at the address, an error when there is none
+    Hle(Routines),
+    // run the machine code on an emulated CDP1802, see chip8::cdp1802
+    Native,
+}
+
+impl SysPolicy {
+    // Whether SYS addr does something else than failing
+    pub fn handles(&self, addr: u16) -> bool {
+        match self {
+            SysPolicy::Ignore | SysPolicy::Native => true,
+            SysPolicy::Error => false,
+            SysPolicy::Hle(routines) => routines.get(addr).is_some(),
+        }
+    }
+}
+
+// The routines of one program by address
+#[derive(Clone, Default)]
+pub struct Routines {
+    routines: BTreeMap<u16, Routine>,
+}
+
+impl Routines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, addr: u16, routine: Routine) {
+        self.routines.insert(addr, routine);
+    }
+
+    pub fn get(&self, addr: u16) -> Option<Routine> {
+        self.routines.get(&addr).copied()
+    }
+
+    // Reads `ADDR NAME` lines naming built-in routines, # starts a comment :
+    //
+    //   # fills the screen then returns
+    //   2F0 clear
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut routines = Self::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (addr, name) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("line {} : expected an address and a routine", n + 1))?;
+            let digits = addr.trim_start_matches("0x").trim_start_matches("0X");
+            let addr = u16::from_str_radix(digits, 16)
+                .ok()
+                .filter(|addr| *addr <= 0xFFF)
+                .ok_or_else(|| format!("line {} : bad address {}", n + 1, addr))?;
+            let routine = builtin(name.trim())
+                .ok_or_else(|| format!("line {} : unknown routine {}", n + 1, name.trim()))?;
+            routines.register(addr, routine);
+        }
+        Ok(routines)
+    }
+}
+
+// The routines found in VIP programs, by name
+pub fn builtin(name: &str) -> Option<Routine> {
+    let routine: Routine = match name {
+        "nop" => |_| {},
+        "clear" => |cpu| cpu.display.clear(),
+        "fill" => |cpu| {
+            for y in 0..32 {
+                for x in 0..64 {
+                    cpu.display[(y, x)] = true;
+                }
+            }
+        },
+        "invert" => |cpu| {
+            for y in 0..32 {
+                for x in 0..64 {
+                    cpu.display[(y, x)] = !cpu.display[(y, x)];
+                }
+            }
+        },
+        "scroll-up" => |cpu| scroll(cpu, -1),
+        "scroll-down" => |cpu| scroll(cpu, 1),
+        _ => return None,
+    };
+    Some(routine)
+}
+
+// Moves the screen by `rows`, the rows coming in are blank
+fn scroll(cpu: &mut Cpu, rows: i32) {
+    let display = &mut cpu.display;
+    let lines: Vec<i32> = if rows > 0 {
+        (0..32).rev().collect()
+    } else {
+        (0..32).collect()
+    };
+    for y in lines {
+        let from = y - rows;
+        for x in 0..64 {
+            display[(y as usize, x)] = (0..32).contains(&from) && display[(from as usize, x)];
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use crate::chip8::database::Entry;
+use crate::chip8::machine::{AudioSink, Clock, Exit, FixedRate, InputSource, VideoSink, AZERTY};
+use crate::screen::Screen;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Glyphs {
+    // 64x16 characters
+    HalfBlocks,
+    // 32x8 characters, for small terminals
+    Braille,
+}
+
+impl Glyphs {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "half-blocks" => Some(Glyphs::HalfBlocks),
+            "braille" => Some(Glyphs::Braille),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn rows(self) -> u16 {
+        match self {
+            Glyphs::HalfBlocks => 16,
+            Glyphs::Braille => 8,
+        }
+    }
+}
+
+// Draws in the console in raw mode on the alternate screen, restored when dropped.
+// Most terminals only report key presses, a key is then held until no press
+// or auto-repeat came for `release_after`
+pub struct Terminal {
+    pub glyphs: Glyphs,
+    pub foreground: Color,
+    pub background: Color,
+    pub release_after: Duration,
+    // the keys bound to the keypad 0 to F
+    pub keymap: [char; 16],
+    // more keys bound to the keypad, the arrows of a game for example
+    pub bindings: Vec<(KeyCode, usize)>,
+    // when each key was last pressed, None when released
+    held: [Option<Instant>; 16],
+    // the terminal reports releases itself
+    releases: bool,
+    // shown under the screen
+    title: String,
+    exit: Exit,
+    beeping: bool,
+    clock: FixedRate,
+    out: Stdout,
+}
+
+impl Terminal {
+    pub fn new(fps: u32) -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+        let releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if releases {
+            execute!(
+                out,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+
+        Ok(Self {
+            glyphs: Glyphs::HalfBlocks,
+            foreground: Color::White,
+            background: Color::Black,
+            release_after: Duration::from_millis(250),
+            keymap: AZERTY,
+            bindings: Vec::new(),
+            held: [None; 16],
+            releases,
+            title: String::new(),
+            exit: Exit::Quit,
+            beeping: false,
+            clock: FixedRate::new(fps),
+            out,
+        })
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        let _ = self.status("");
+    }
+
+    // The colours and the keys of a game of the database, the arrows, space
+    // for a and enter for b, or the default ones
+    pub fn apply(&mut self, entry: Option<&Entry>) {
+        self.foreground = Color::White;
+        self.background = Color::Black;
+        self.bindings.clear();
+        let Some(entry) = entry else {
+            return;
+        };
+        if let Some(colors) = &entry.colors {
+            if let [[r, g, b], [fr, fg, fb], ..] = colors.pixels[..] {
+                self.background = Color::Rgb { r, g, b };
+                self.foreground = Color::Rgb {
+                    r: fr,
+                    g: fg,
+                    b: fb,
+                };
+            }
+        }
+        for (name, &key) in &entry.keys {
+            let code = match name.as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "a" => KeyCode::Char(' '),
+                "b" => KeyCode::Enter,
+                _ => continue,
+            };
+            self.bindings.push((code, key as usize & 0xF));
+        }
+    }
+
+    fn status(&mut self, text: &str) -> io::Result<()> {
+        let line = format!("{}  {}", self.title, text);
+        queue!(
+            self.out,
+            MoveTo(0, self.glyphs.rows()),
+            ResetColor,
+            Clear(ClearType::CurrentLine),
+            Print(line.trim())
+        )?;
+        self.out.flush()
+    }
+
+    // Holds or releases the keypad key bound to a key event
+    pub(crate) fn press(&mut self, key: &KeyEvent) {
+        let bound = self.bindings.iter().find(|(code, _)| *code == key.code);
+        let k = match (bound, key.code) {
+            (Some(&(_, k)), _) => Some(k),
+            (None, KeyCode::Char(c)) => self.keymap.iter().position(|b| b.eq_ignore_ascii_case(&c)),
+            _ => None,
+        };
+        if let Some(k) = k {
+            self.held[k] = match key.kind {
+                KeyEventKind::Release => None,
+                _ => Some(Instant::now()),
+            };
+        }
+    }
+
+    // Copies the held keys, releasing the ones pressed too long ago
+    pub(crate) fn update_keys(&mut self, keys: &mut [bool; 16]) {
+        let now = Instant::now();
+        for (key, held) in keys.iter_mut().zip(self.held.iter_mut()) {
+            if !self.releases && held.is_some_and(|t| now - t > self.release_after) {
+                *held = None;
+            }
+            *key = held.is_some();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) -> io::Result<()> {
+        execute!(self.out, ResetColor, Clear(ClearType::All))
+    }
+
+    // Draws the screen from (column, row)
+    pub(crate) fn draw(&mut self, screen: &Screen, column: u16, row: u16) -> io::Result<()> {
+        let text = match self.glyphs {
+            Glyphs::HalfBlocks => screen.half_blocks().to_string(),
+            Glyphs::Braille => screen.braille().to_string(),
+        };
+        queue!(
+            self.out,
+            SetForegroundColor(self.foreground),
+            SetBackgroundColor(self.background)
+        )?;
+        for (i, line) in text.lines().enumerate() {
+            queue!(self.out, MoveTo(column, row + i as u16), Print(line))?;
+        }
+        queue!(self.out, ResetColor)
+    }
+
+    pub(crate) fn out(&mut self) -> &mut Stdout {
+        &mut self.out
+    }
+}
+
+// Esc and Ctrl-C
+pub(crate) fn quits(key: &KeyEvent) -> bool {
+    key.kind != KeyEventKind::Release
+        && (key.code == KeyCode::Esc
+            || key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL))
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.releases {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(self.out, ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl VideoSink for Terminal {
+    fn present(&mut self, screen: &Screen) {
+        let _ = self.draw(screen, 0, 0);
+        let _ = self.out.flush();
+    }
+
+    fn waiting_for_key(&mut self, waiting: bool) {
+        let _ = self.status(if waiting { "waiting for a key" } else { "" });
+    }
+}
+
+impl InputSource for Terminal {
+    fn poll(&mut self, keys: &mut [bool; 16]) -> bool {
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            match event::read() {
+                Ok(Event::Key(key)) if quits(&key) => {
+                    self.exit = Exit::Quit;
+                    return false;
+                }
+                Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => match key.code {
+                    KeyCode::PageDown => {
+                        self.exit = Exit::Next;
+                        return false;
+                    }
+                    KeyCode::PageUp => {
+                        self.exit = Exit::Previous;
+                        return false;
+                    }
+                    _ => self.press(&key),
+                },
+                Ok(Event::Key(key)) => self.press(&key),
+                Ok(Event::Resize(..)) => {
+                    let _ = self.clear();
+                }
+                Ok(_) => {}
+                Err(_) => {
+                    self.exit = Exit::Quit;
+                    return false;
+                }
+            }
+        }
+        self.update_keys(keys);
+        true
+    }
+
+    fn exit(&mut self) -> Exit {
+        self.exit
+    }
+}
+
+impl AudioSink for Terminal {
+    // the bell rings once when the buzzer starts
+    fn set_tone(&mut self, on: bool) {
+        if on && !self.beeping {
+            let _ = execute!(self.out, Print('\x07'));
+        }
+        self.beeping = on;
+    }
+}
+
+impl Clock for Terminal {
+    fn wait_frame(&mut self) {
+        self.clock.wait_frame()
+    }
+}
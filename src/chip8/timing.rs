@@ -0,0 +1,85 @@
+use crate::chip8::cpu::Cpu;
+use crate::chip8::instructions::Instruction;
+
+// The VIP runs its 1802 at 1.7609 MHz with 8 clocks per machine cycle,
+// which gives 3668 machine cycles between two 60 Hz interrupts
+pub const CYCLES_PER_FRAME: i32 = 3668;
+// Cycles taken every frame by the CDP1861 display DMA and the interrupt routine
+// updating the timers, the interpreter only gets what is left
+pub const INTERRUPT_CYCLES: i32 = 1024 + 48;
+
+// Machine cycles taken by the VIP interpreter to execute an instruction
+// in the given cpu state, after the table of Jackson Sommerich's "Chip-8
+// Instruction Scheduling and Frequency" counted from the interpreter listing
+pub fn cycles(instruction: &Instruction, cpu: &Cpu) -> i32 {
+    let v = |x: u8| cpu.registers[x as usize];
+    // the taken branch of a skip costs an extra fetch
+    let skip = |taken: bool| if taken { 14 } else { 10 };
+
+    match *instruction {
+        Instruction::Cls => 24,
+        Instruction::Ret => 10,
+        // a call into machine code, the routine itself is not counted
+        Instruction::SysAddr { .. } => 26,
+        Instruction::JpAddr { .. } => 12,
+        Instruction::CallAddr { .. } => 26,
+        Instruction::SeVxByte { x, byte } => skip(v(x) == byte),
+        Instruction::SneVxByte { x, byte } => skip(v(x) != byte),
+        Instruction::SeVxVy { x, y } => skip(v(x) == v(y)) + 4,
+        Instruction::LdVxByte { .. } => 6,
+        Instruction::AddVxByte { .. } => 10,
+        Instruction::LdVxVy { .. } => 12,
+        Instruction::OrVxVy { .. }
+        | Instruction::AndVxVy { .. }
+        | Instruction::XorVxVy { .. }
+        | Instruction::AddVxVy { .. }
+        | Instruction::SubVxVy { .. }
+        | Instruction::ShrVxVy { .. }
+        | Instruction::SubnVxVy { .. }
+        | Instruction::ShlVxVy { .. } => 44,
+        Instruction::SneVxVy { x, y } => skip(v(x) != v(y)) + 4,
+        Instruction::LdIAddr { .. } => 12,
+        // crossing a page costs two more cycles
+        Instruction::JpV0Addr { addr } => {
+            if (addr & 0xFF) + v(0) as u16 > 0xFF {
+                24
+            } else {
+                22
+            }
+        }
+        Instruction::RndVxByte { .. } => 36,
+        Instruction::DrwVxVyNibble { x, nibble, .. } => {
+            // a sprite not aligned on a byte spans two bytes of the frame buffer per row
+            let row = if v(x) % 8 == 0 { 21 } else { 35 };
+            26 + row * nibble as i32
+        }
+        Instruction::SkpVx { x } => skip(cpu.keys[(v(x) & 0x0F) as usize]) + 4,
+        Instruction::SknpVx { x } => skip(!cpu.keys[(v(x) & 0x0F) as usize]) + 4,
+        Instruction::LdVxDt { .. } => 10,
+        Instruction::LdVxK { .. } => 10,
+        Instruction::LdDtVx { .. } => 10,
+        Instruction::LdStVx { .. } => 10,
+        Instruction::AddIVx { .. } => 16,
+        Instruction::LdFVx { .. } => 16,
+        Instruction::LdBVx { x } => 84 + 16 * (v(x) / 10 + v(x) % 10) as i32,
+        Instruction::LdIVx { x } | Instruction::LdVxI { x } => 16 + 14 * (x as i32 + 1),
+    }
+}
+
+// Cycles left in the current frame, carried over when an instruction
+// runs past the interrupt
+pub struct VipClock {
+    pub budget: i32,
+}
+
+impl VipClock {
+    pub fn new() -> Self {
+        Self { budget: 0 }
+    }
+}
+
+impl Default for VipClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,210 @@
+use chip8::chip8::machine::{AZERTY, QWERTY};
+use chip8::chip8::platform::Platform;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "chip8", version, about = "A CHIP-8 interpreter")]
+#[command(
+    after_help = "Without a command the launcher lists the roms of the current directory, \
+                        and `chip8 ROM` is short for `chip8 run ROM`."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub options: Options,
+}
+
+// How the roms run, given before or after the command
+#[derive(Args)]
+pub struct Options {
+    /// Quirks of chip-8, schip or xo-chip, the database chooses for the roms it knows
+    #[arg(long, global = true, value_parser = platform)]
+    pub platform: Option<Platform>,
+    /// Quirks changed after the platform, like vf_reset=off,shifting=on
+    #[arg(long, global = true, value_parser = quirks)]
+    pub quirks: Option<String>,
+    /// Instructions run every frame
+    #[arg(long, global = true, visible_alias = "tickrate")]
+    pub speed: Option<u32>,
+    /// Runs as many instructions as a COSMAC VIP in a frame
+    #[arg(long, global = true)]
+    pub cycle_accurate: bool,
+    /// Frames per second
+    #[arg(long, global = true, default_value_t = 60)]
+    pub fps: u32,
+    /// Window pixels for a CHIP-8 pixel
+    #[arg(long, global = true, default_value_t = 10)]
+    pub scale: u32,
+    /// Colours of the pixels and of the background, like #ffffff,#000000
+    #[arg(long, global = true, value_parser = palette)]
+    pub palette: Option<[[u8; 3]; 2]>,
+    /// Keys bound to the keypad 0 to F : azerty, qwerty or 16 characters
+    #[arg(long, global = true, value_parser = keymap)]
+    pub keymap: Option<[char; 16]>,
+    /// Loads the rom at another address than 200, 600 for the ETI-660
+    #[arg(long, global = true, value_parser = address)]
+    pub load_address: Option<u16>,
+    /// What SYS does, without it the routines of a .sys file next to the rom are emulated
+    #[arg(long, global = true, value_enum)]
+    pub sys: Option<Sys>,
+    /// The SYS routines to emulate, one ADDR NAME per line
+    #[arg(long, global = true, conflicts_with = "sys")]
+    pub sys_routines: Option<PathBuf>,
+    /// A programs.json of the CHIP-8 database instead of the embedded one
+    #[arg(long, global = true)]
+    pub database: Option<PathBuf>,
+    /// Ignores the database
+    #[arg(long, global = true)]
+    pub no_database: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Sys {
+    Ignore,
+    Error,
+    Native,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Runs a rom, an archive or a directory of roms
+    Run {
+        /// A rom, a .zip or .gz archive or a directory
+        path: PathBuf,
+        /// Starts on this rom of the archive or the directory
+        #[arg(long)]
+        rom: Option<String>,
+        /// Draws in the console instead of a window
+        #[arg(long)]
+        terminal: bool,
+        /// Opens the debugger in the console
+        #[arg(long)]
+        debug: bool,
+        /// Characters drawing the screen in the console
+        #[arg(long, value_parser = ["half-blocks", "braille"])]
+        glyphs: Option<String>,
+        /// Milliseconds a key stays held in the console without a repeat
+        #[arg(long)]
+        key_timeout: Option<u64>,
+    },
+    /// Lists the roms of a directory in a window to pick one
+    Launcher {
+        /// The current directory by default
+        dir: Option<PathBuf>,
+    },
+    /// Prints the address, the opcode and the instruction of every word
+    Disasm { rom: PathBuf },
+    /// Assembles instructions, one per line, the output of disasm included
+    Asm {
+        source: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Prints the hashes, the size, the guessed platform and the database entry
+    Info {
+        #[arg(required = true)]
+        roms: Vec<PathBuf>,
+    },
+    /// Prints every instruction executed with the registers after it
+    Trace {
+        rom: PathBuf,
+        #[arg(long, default_value_t = 1000)]
+        steps: u64,
+    },
+    /// Runs frames without a window as fast as possible
+    Bench {
+        rom: PathBuf,
+        #[arg(long, default_value_t = 600)]
+        frames: u64,
+        /// Keeps the decoded instructions
+        #[arg(long)]
+        cached: bool,
+    },
+    /// Lists the problems of a rom and guesses its platform
+    Lint { rom: PathBuf },
+    /// Exports the control-flow graph as Graphviz DOT or JSON
+    Cfg {
+        rom: PathBuf,
+        #[arg(long)]
+        json: bool,
+        /// Only the subroutine at this address
+        #[arg(long, value_parser = address)]
+        sub: Option<u16>,
+    },
+    /// Recompiles a rom to a Rust file, or to a crate when OUTPUT is not a .rs
+    Recompile {
+        rom: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// The crate depends on the chip8 checkout at this path instead of its released version
+        #[arg(long)]
+        chip8_path: Option<PathBuf>,
+    },
+    /// Searches the titles of the database
+    Db { text: String },
+    /// Lists the roms of an archive or a directory with their SHA-1
+    List { path: PathBuf },
+}
+
+impl Cli {
+    // Parses the arguments, `chip8 ROM` being `chip8 run ROM`
+    pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Self {
+        let mut args: Vec<OsString> = args.into_iter().collect();
+        let command = Self::command();
+        let first = args.get(1).and_then(|a| a.to_str());
+        let is_rom = first.is_some_and(|first| {
+            !first.starts_with('-')
+                && first != "help"
+                && command.get_subcommands().all(|c| c.get_name() != first)
+        });
+        if is_rom {
+            args.insert(1, OsString::from("run"));
+        }
+        Self::parse_from(args)
+    }
+}
+
+fn platform(name: &str) -> Result<Platform, String> {
+    Platform::from_name(name).ok_or_else(|| String::from("expected chip-8, schip or xo-chip"))
+}
+
+fn quirks(flags: &str) -> Result<String, String> {
+    Platform::Chip8.quirks().update(flags)?;
+    Ok(flags.to_string())
+}
+
+pub fn address(text: &str) -> Result<u16, String> {
+    let digits = text.trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(digits, 16).map_err(|_| String::from("expected a hexadecimal address"))
+}
+
+fn palette(text: &str) -> Result<[[u8; 3]; 2], String> {
+    let colour = |c: &str| {
+        let digits = c.trim().trim_start_matches('#');
+        match u32::from_str_radix(digits, 16) {
+            Ok(v) if digits.len() == 6 => Ok([(v >> 16) as u8, (v >> 8) as u8, v as u8]),
+            _ => Err(format!("{} : expected a colour like #ff8000", c)),
+        }
+    };
+    match text.split_once(',') {
+        Some((pixels, background)) => Ok([colour(pixels)?, colour(background)?]),
+        None => Err(String::from(
+            "expected two colours, the pixels then the background",
+        )),
+    }
+}
+
+fn keymap(text: &str) -> Result<[char; 16], String> {
+    match text {
+        "azerty" => Ok(AZERTY),
+        "qwerty" => Ok(QWERTY),
+        _ => {
+            let keys: Vec<char> = text.chars().collect();
+            keys.try_into()
+                .map_err(|_| String::from("expected azerty, qwerty or 16 characters"))
+        }
+    }
+}
@@ -1,207 +1,617 @@
-mod chip8;
-mod screen;
-
-use crate::chip8::interpreter::Interpreter;
-use std::env;
-
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
+use chip8::chip8::archive::{self, Rom};
+use chip8::chip8::assembler::assemble_line;
+use chip8::chip8::cfg::Cfg;
+#[cfg(feature = "terminal")]
+use chip8::chip8::dashboard::Dashboard;
+use chip8::chip8::database::{Database, Entry};
+#[cfg(feature = "terminal")]
+use chip8::chip8::debugger::Debugger;
+use chip8::chip8::disassembler::Disassembler;
+use chip8::chip8::interpreter::Interpreter;
+#[cfg(feature = "sdl")]
+use chip8::chip8::launcher::Launcher;
+use chip8::chip8::lint::lint;
+use chip8::chip8::machine::{Headless, Machine};
+#[cfg(any(feature = "sdl", feature = "terminal"))]
+use chip8::chip8::machine::{Exit, Frontend};
+use chip8::chip8::program::{LoadError, Program};
+use chip8::chip8::recompiler;
+use chip8::chip8::sys::{Routines, SysPolicy};
+#[cfg(feature = "terminal")]
+use chip8::chip8::terminal::{Glyphs, Terminal};
+use cli::{Cli, Command, Options, Sys};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "terminal")]
 use std::time::Duration;
+use std::time::Instant;
+
+mod cli;
+#[cfg(feature = "sdl")]
+mod sdl;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse_args(std::env::args_os());
+    let options = &cli.options;
 
-    let program = chip8::program::Program::from(String::from(&args[1]));
-    let mut interpreter = Interpreter::new();
-    interpreter.load_program(&program);
-
-
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
-
-    let window = video_subsystem
-        .window(&args[1], 640, 320)
-        .position_centered()
-        .build()
-        .unwrap();
-
-    let mut canvas = window.into_canvas().build().unwrap();
-
-    canvas.clear();
-    canvas.present();
-
-    let mut event_pump = sdl_context.event_pump().unwrap();
-    'running: loop {
-        canvas.clear();
-
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num1),
-                    ..
-                } => interpreter.cpu.keys[0] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num2),
-                    ..
-                } => interpreter.cpu.keys[1] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num3),
-                    ..
-                } => interpreter.cpu.keys[2] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Num4),
-                    ..
-                } => interpreter.cpu.keys[3] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::A),
-                    ..
-                } => interpreter.cpu.keys[4] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Z),
-                    ..
-                } => interpreter.cpu.keys[5] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::E),
-                    ..
-                } => interpreter.cpu.keys[6] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::R),
-                    ..
-                } => interpreter.cpu.keys[7] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Q),
-                    ..
-                } => interpreter.cpu.keys[8] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::S),
-                    ..
-                } => interpreter.cpu.keys[9] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::D),
-                    ..
-                } => interpreter.cpu.keys[10] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::F),
-                    ..
-                } => interpreter.cpu.keys[11] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::W),
-                    ..
-                } => interpreter.cpu.keys[12] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::X),
-                    ..
-                } => interpreter.cpu.keys[13] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::C),
-                    ..
-                } => interpreter.cpu.keys[14] = true,
-                Event::KeyDown {
-                    keycode: Some(Keycode::V),
-                    ..
-                } => interpreter.cpu.keys[15] = true,
-
-                Event::KeyUp {
-                    keycode: Some(Keycode::Num1),
-                    ..
-                } => interpreter.cpu.keys[0] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::Num2),
-                    ..
-                } => interpreter.cpu.keys[1] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::Num3),
-                    ..
-                } => interpreter.cpu.keys[2] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::Num4),
-                    ..
-                } => interpreter.cpu.keys[3] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::A),
-                    ..
-                } => interpreter.cpu.keys[4] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::Z),
-                    ..
-                } => interpreter.cpu.keys[5] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::E),
-                    ..
-                } => interpreter.cpu.keys[6] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::R),
-                    ..
-                } => interpreter.cpu.keys[7] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::Q),
-                    ..
-                } => interpreter.cpu.keys[8] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::S),
-                    ..
-                } => interpreter.cpu.keys[9] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::D),
-                    ..
-                } => interpreter.cpu.keys[10] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::F),
-                    ..
-                } => interpreter.cpu.keys[11] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::W),
-                    ..
-                } => interpreter.cpu.keys[12] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::X),
-                    ..
-                } => interpreter.cpu.keys[13] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::C),
-                    ..
-                } => interpreter.cpu.keys[14] = false,
-                Event::KeyUp {
-                    keycode: Some(Keycode::V),
-                    ..
-                } => interpreter.cpu.keys[15] = false,
-                _ => {}
+    match cli.command {
+        None => launch(options, Path::new(".")),
+        Some(Command::Launcher { dir }) => {
+            launch(options, dir.as_deref().unwrap_or(Path::new(".")))
+        }
+        Some(Command::Run {
+            path,
+            rom,
+            terminal,
+            debug,
+            glyphs,
+            key_timeout,
+        }) => {
+            let console = Console {
+                debug,
+                glyphs,
+                key_timeout,
+            };
+            run(options, &path, rom.as_deref(), terminal || debug, &console)
+        }
+        Some(Command::Disasm { rom }) => disasm(&load(&rom)),
+        Some(Command::Asm { source, output }) => asm(&source, &output),
+        Some(Command::Info { roms }) => info(options, &roms),
+        Some(Command::Trace { rom, steps }) => trace(options, &rom, steps),
+        Some(Command::Bench {
+            rom,
+            frames,
+            cached,
+        }) => bench(options, &rom, frames, cached),
+        Some(Command::Lint { rom }) => {
+            let report = lint(&load(&rom));
+            print!("{}", report);
+            if report.has_errors() {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Cfg { rom, json, sub }) => {
+            let cfg = Cfg::build(&load(&rom));
+            if json {
+                println!("{}", cfg.to_json(sub));
+            } else {
+                print!("{}", cfg.to_dot(sub));
+            }
+        }
+        Some(Command::Recompile {
+            rom,
+            output,
+            chip8_path,
+        }) => export_rust(&rom, &output, chip8_path.as_deref()),
+        Some(Command::Db { text }) => {
+            for (sha1, title) in database(options).search(&text) {
+                println!("{}  {}", sha1, title);
             }
         }
-        // The rest of the game loop goes here...
-        interpreter.step();
+        Some(Command::List { path }) => list(options, &path),
+    }
+}
+
+// The options of the console frontend
+#[cfg_attr(not(feature = "terminal"), allow(dead_code))]
+struct Console {
+    debug: bool,
+    glyphs: Option<String>,
+    key_timeout: Option<u64>,
+}
+
+// chip8 run ROM|ARCHIVE|DIR [--rom NAME]
+fn run(options: &Options, path: &Path, rom: Option<&str>, terminal: bool, console: &Console) {
+    let roms = exit_on_error(path, archive::open(path));
+    // --rom NAME starts on another rom of an archive or a directory
+    let first = match rom {
+        Some(name) => roms.iter().position(|r| r.name == name).unwrap_or_else(|| {
+            fail(format!("{} : no rom named {}", path.display(), name));
+        }),
+        None => 0,
+    };
+    let database = enabled_database(options);
+    let player = Player {
+        options,
+        path,
+        roms: &roms,
+        database: database.as_ref(),
+    };
+    // the first rom is checked before opening the window
+    exit_on_error(Path::new(&roms[first].name), player.setup(first));
+
+    if cfg!(not(feature = "sdl")) || terminal {
+        run_terminal(&player, first, console);
+    } else {
+        run_sdl(&player, first);
+    }
+}
+
+// Plays the roms of a file, an archive or a directory
+struct Player<'a> {
+    options: &'a Options,
+    // the file, the archive or the directory
+    path: &'a Path,
+    roms: &'a [Rom],
+    database: Option<&'a Database>,
+}
+
+impl Player<'_> {
+    // The interpreter running the rom `i` and what the database knows about
+    // it, the options given override the database
+    fn setup(&self, i: usize) -> Result<(Interpreter, Option<Entry>), LoadError> {
+        let rom = &self.roms[i];
+        let path = if self.path.is_dir() {
+            self.path.join(&rom.name)
+        } else {
+            self.path.to_path_buf()
+        };
+        setup(
+            self.options,
+            &rom.program,
+            &path,
+            self.database,
+            Interpreter::new(),
+        )
+    }
+
+    #[cfg(any(feature = "sdl", feature = "terminal"))]
+    // The title of the database or the name of the rom, with its position
+    fn title(&self, i: usize, entry: Option<&Entry>) -> String {
+        let title = entry.map_or(&self.roms[i].name, |e| &e.title);
+        match self.roms.len() {
+            1 => title.clone(),
+            n => format!("{} ({}/{})", title, i + 1, n),
+        }
+    }
 
-        for y in 0..32 {
-            for x in 0..64 {
-                if interpreter.cpu.display[(y, x)] {
-                    canvas.set_draw_color(Color::BLACK);
-                } else {
-                    canvas.set_draw_color(Color::WHITE);
+    #[cfg(any(feature = "sdl", feature = "terminal"))]
+    // Runs the roms from `current` until the user quits or goes back to the
+    // launcher, PageDown and PageUp switch to the next and the previous one.
+    // `show` gives the frontend the title and the settings of the rom
+    fn play<F: Frontend>(
+        &self,
+        mut frontend: F,
+        current: &mut usize,
+        show: impl Fn(&mut F, &Options, &str, Option<&Entry>),
+    ) -> (F, Exit) {
+        let count = self.roms.len();
+        loop {
+            let i = *current;
+            let (interpreter, entry) = match self.setup(i) {
+                Ok(setup) => setup,
+                Err(e) => {
+                    drop(frontend);
+                    fail(format!("{} : {}", self.roms[i].name, e));
                 }
-                canvas
-                    .fill_rect(sdl2::rect::Rect::new(
-                        (x * 10) as i32,
-                        (y * 10) as i32,
-                        10,
-                        10,
-                    ))
-                    .unwrap();
+            };
+            show(
+                &mut frontend,
+                self.options,
+                &self.title(i, entry.as_ref()),
+                entry.as_ref(),
+            );
+            let mut machine = Machine::new(interpreter, frontend);
+            let exit = machine.run();
+            frontend = machine.frontend;
+            *current = match exit {
+                Exit::Quit | Exit::Menu => return (frontend, exit),
+                Exit::Next => (i + 1) % count,
+                Exit::Previous => (i + count - 1) % count,
+            };
+        }
+    }
+}
+
+// Loads `program` into `interpreter` with the settings of the database, then
+// those of the command line
+fn setup(
+    options: &Options,
+    program: &Program,
+    path: &Path,
+    database: Option<&Database>,
+    mut interpreter: Interpreter,
+) -> Result<(Interpreter, Option<Entry>), LoadError> {
+    let entry = database.and_then(|d| d.lookup(program));
+    let mut program = program.clone();
+    let address = options
+        .load_address
+        .or(entry.as_ref().and_then(|e| e.start_address));
+    if let Some(address) = address {
+        program = program.at(address)?;
+    }
+    let platform = options.platform;
+    if let Some(platform) = platform.or(entry.as_ref().and_then(|e| e.platform)) {
+        program.check(platform)?;
+    }
+
+    interpreter.load_program(&program)?;
+    interpreter.set_cycle_accurate(options.cycle_accurate);
+    if let Some(entry) = &entry {
+        entry.apply(&mut interpreter);
+    }
+    if let Some(platform) = platform {
+        interpreter.quirks = platform.quirks();
+    }
+    if let Some(flags) = &options.quirks {
+        // checked while parsing
+        interpreter.quirks.update(flags).unwrap();
+    }
+    if let Some(speed) = options.speed {
+        interpreter.tickrate = speed;
+    }
+    interpreter.sys = sys_policy(options, path);
+    Ok((interpreter, entry))
+}
+
+#[cfg(feature = "sdl")]
+fn show_sdl(window: &mut sdl::Sdl, options: &Options, title: &str, entry: Option<&Entry>) {
+    window.set_title(title);
+    window.apply(entry);
+    if let Some([[r, g, b], [br, bg, bb]]) = options.palette {
+        window.foreground = sdl2::pixels::Color::RGB(r, g, b);
+        window.background = sdl2::pixels::Color::RGB(br, bg, bb);
+    }
+}
+
+#[cfg(feature = "sdl")]
+fn window(options: &Options, title: &str) -> sdl::Sdl {
+    let mut window = sdl::Sdl::new(title, options.fps, options.scale);
+    if let Some(keymap) = options.keymap {
+        window.keymap = keymap;
+    }
+    window
+}
+
+#[cfg(feature = "sdl")]
+fn run_sdl(player: &Player, first: usize) {
+    let window = window(player.options, &player.roms[first].name);
+    let mut current = first;
+    player.play(window, &mut current, show_sdl);
+}
+
+#[cfg(not(feature = "sdl"))]
+fn run_sdl(_player: &Player, _first: usize) {
+    unreachable!("the terminal is used without the sdl feature")
+}
+
+#[cfg(feature = "terminal")]
+fn show_terminal(terminal: &mut Terminal, options: &Options, title: &str, entry: Option<&Entry>) {
+    terminal.apply(entry);
+    if let Some([[r, g, b], [br, bg, bb]]) = options.palette {
+        use crossterm::style::Color;
+        terminal.foreground = Color::Rgb { r, g, b };
+        terminal.background = Color::Rgb {
+            r: br,
+            g: bg,
+            b: bb,
+        };
+    }
+    terminal.set_title(title);
+}
+
+// --terminal [--debug] [--glyphs half-blocks|braille] [--key-timeout MS]
+#[cfg(feature = "terminal")]
+fn run_terminal(player: &Player, first: usize, console: &Console) {
+    let options = player.options;
+    let mut terminal = match Terminal::new(options.fps) {
+        Ok(terminal) => terminal,
+        Err(e) => fail(format!("cannot use the terminal : {}", e)),
+    };
+    if let Some(glyphs) = &console.glyphs {
+        // checked while parsing
+        terminal.glyphs = Glyphs::from_name(glyphs).unwrap();
+    }
+    if let Some(ms) = console.key_timeout {
+        terminal.release_after = Duration::from_millis(ms);
+    }
+    if let Some(keymap) = options.keymap {
+        terminal.keymap = keymap;
+    }
+    if console.debug {
+        // checked by run
+        let (interpreter, entry) = player.setup(first).unwrap();
+        show_terminal(
+            &mut terminal,
+            options,
+            &player.title(first, entry.as_ref()),
+            entry.as_ref(),
+        );
+        let mut dashboard = Dashboard::new(Debugger::new(interpreter), terminal);
+        if let Err(e) = dashboard.run() {
+            drop(dashboard);
+            fail(format!("terminal error : {}", e));
+        }
+        return;
+    }
+    let mut current = first;
+    player.play(terminal, &mut current, show_terminal);
+}
+
+#[cfg(not(feature = "terminal"))]
+fn run_terminal(_player: &Player, _first: usize, _console: &Console) {
+    fail("built without the terminal feature".to_string());
+}
+
+// The SDL launcher over the roms of `dir`, Backspace goes back to it from a game
+#[cfg(feature = "sdl")]
+fn launch(options: &Options, dir: &Path) {
+    let database = enabled_database(options);
+    let launcher = exit_on_error(dir, Launcher::scan(dir, database.as_ref()));
+    let player = Player {
+        options,
+        path: dir,
+        roms: &launcher.roms,
+        database: database.as_ref(),
+    };
+    let mut window = window(options, "chip8");
+    window.launcher = true;
+    let mut selected = 0;
+    while let Some(i) = window.pick(&launcher, selected) {
+        selected = i;
+        let (back, exit) = player.play(window, &mut selected, show_sdl);
+        if exit == Exit::Quit {
+            return;
+        }
+        window = back;
+    }
+}
+
+#[cfg(not(feature = "sdl"))]
+fn launch(_options: &Options, _dir: &Path) {
+    fail("the launcher needs the sdl feature, see chip8 --help".to_string());
+}
+
+// Every word with its address and opcode, a trailing odd byte alone
+fn disasm(program: &Program) {
+    let mut address = program.address;
+    for word in program.content.chunks(2) {
+        match *word {
+            [high, low] => {
+                let op = u16::from_be_bytes([high, low]);
+                let mut disassembler = Disassembler::new();
+                disassembler.step(op);
+                print!("{:03X}  {:04X}  {}", address, op, disassembler);
             }
+            [byte] => println!("{:03X}  {:02X}", address, byte),
+            _ => unreachable!(),
         }
+        address += 2;
+    }
+}
 
-        canvas.present();
-        ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
-        if interpreter.cpu.delay_timer > 0 {
-            interpreter.cpu.delay_timer -= 1;
+// Assembles one instruction per line, the address and opcode columns of
+// disasm are skipped so that its output assembles back
+fn asm(source: &Path, output: &Path) {
+    let text = std::fs::read_to_string(source).unwrap_or_else(|e| fail(cannot_read(source, &e)));
+    let mut bytes = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let code = line.split(';').next().unwrap_or("");
+        let mut words = code.split_whitespace();
+        let code = match (words.next(), words.next()) {
+            (Some(address), Some(op))
+                if address.len() == 3
+                    && op.len() == 4
+                    && u16::from_str_radix(address, 16).is_ok()
+                    && u16::from_str_radix(op, 16).is_ok() =>
+            {
+                words.collect::<Vec<_>>().join(" ")
+            }
+            _ => code.to_string(),
+        };
+        if code.trim().is_empty() {
+            continue;
         }
-        if interpreter.cpu.sound_timer > 0 {
-            interpreter.cpu.sound_timer -= 1;
+        match assemble_line(&code) {
+            Ok(op) => bytes.extend_from_slice(&op.to_be_bytes()),
+            Err(e) => fail(format!("{}:{}: {}", source.display(), n + 1, e)),
+        }
+    }
+    if let Err(e) = std::fs::write(output, bytes) {
+        fail(format!("cannot write {} : {}", output.display(), e));
+    }
+}
+
+// The hashes, the size, the platform lint guesses and the database entry
+fn info(options: &Options, roms: &[PathBuf]) {
+    let database = enabled_database(options);
+    for (i, path) in roms.iter().enumerate() {
+        let program = load(path);
+        if i > 0 {
+            println!();
+        }
+        println!("{}", path.display());
+        println!("sha1      {}", program.sha1_hex());
+        println!("crc32     {:08x}", program.crc32());
+        println!("size      {} bytes", program.content.len());
+        println!("detected  {}", lint(&program).platform);
+        match database.as_ref().and_then(|d| d.lookup(&program)) {
+            Some(entry) => print!("{}", entry),
+            None => println!("not in the database"),
+        }
+    }
+}
+
+// Every instruction executed with V0-VF and I after it, until an error or
+// `steps` instructions
+fn trace(options: &Options, path: &Path, steps: u64) {
+    let program = load(path);
+    let database = enabled_database(options);
+    let mut interpreter = Interpreter::new();
+    interpreter.seed_rng(0);
+    let (mut interpreter, _) = exit_on_error(
+        path,
+        setup(options, &program, path, database.as_ref(), interpreter),
+    );
+    for _ in 0..steps {
+        let pc = interpreter.cpu.program_counter;
+        let op = interpreter.peek();
+        if let Err(e) = interpreter.try_step() {
+            // no opcode past the end of memory
+            let op = op.map_or(String::new(), |op| format!("{:04X}  ", op));
+            fail(format!("{:03X}  {}{}", pc, op, e));
+        }
+        // try_step fails without an opcode
+        let op = op.unwrap();
+        let mut disassembler = Disassembler::new();
+        disassembler.step(op);
+        let registers: Vec<String> = interpreter
+            .cpu
+            .registers
+            .iter()
+            .map(|v| format!("{:02X}", v))
+            .collect();
+        println!(
+            "{:03X}  {:04X}  {:<16} V {}  I {:03X}",
+            pc,
+            op,
+            disassembler.to_string().trim_end(),
+            registers.join(" "),
+            interpreter.cpu.index
+        );
+    }
+}
+
+// Runs `frames` frames without a window nor waiting, with the tickrate of the
+// database or of --speed
+fn bench(options: &Options, path: &Path, frames: u64, cached: bool) {
+    let program = load(path);
+    let database = enabled_database(options);
+    let mut interpreter = if cached {
+        Interpreter::with_cache()
+    } else {
+        Interpreter::new()
+    };
+    interpreter.seed_rng(0);
+    let (interpreter, _) = exit_on_error(
+        path,
+        setup(options, &program, path, database.as_ref(), interpreter),
+    );
+    let tickrate = interpreter.tickrate;
+    let mut machine = Machine::new(interpreter, Headless::new(frames));
+    let start = Instant::now();
+    if let Err(e) = machine.try_run() {
+        fail(format!("{} : {}", path.display(), e));
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "{} frames of {} instructions in {:.3} s : {:.0} frames/s",
+        frames,
+        tickrate,
+        elapsed,
+        frames as f64 / elapsed
+    );
+}
+
+// The programs.json of --database FILE or the embedded one
+fn database(options: &Options) -> Database {
+    match &options.database {
+        Some(path) => Database::from_path(path).unwrap_or_else(|e| {
+            fail(format!("{} : {}", path.display(), e));
+        }),
+        None => Database::embedded(),
+    }
+}
+
+// The database, None with --no-database
+fn enabled_database(options: &Options) -> Option<Database> {
+    if options.no_database {
+        return None;
+    }
+    Some(database(options))
+}
+
+// The roms with their SHA-1, their size and their title when the database
+// knows them
+fn list(options: &Options, path: &Path) {
+    let database = enabled_database(options);
+    for rom in exit_on_error(path, archive::open(path)) {
+        let title = database
+            .as_ref()
+            .and_then(|d| d.lookup(&rom.program))
+            .map(|e| e.title);
+        println!(
+            "{}  {:5}  {}{}",
+            rom.program.sha1_hex(),
+            rom.program.content.len(),
+            rom.name,
+            title.map_or(String::new(), |t| format!("  ({})", t))
+        );
+    }
+}
+
+// A single file, or a whole crate when the output is not a .rs
+fn export_rust(path: &Path, output: &Path, chip8_path: Option<&Path>) {
+    let program = load(path);
+    let name = output
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("game");
+
+    let rust =
+        recompiler::recompile(&program, &path.to_string_lossy()) + &recompiler::main_sdl(name);
+    let written = if output.extension().is_some_and(|e| e == "rs") {
+        std::fs::write(output, rust)
+    } else {
+        std::fs::create_dir_all(output.join("src"))
+            .and_then(|_| std::fs::write(output.join("Cargo.toml"), recompiler::manifest(name, chip8_path)))
+            .and_then(|_| std::fs::write(output.join("src").join("main.rs"), rust))
+    };
+    if let Err(e) = written {
+        fail(format!("cannot write {} : {}", output.display(), e));
+    }
+}
+
+// --sys ignore|error|native, or the routines of --sys-routines FILE or of the .sys
+// file next to the rom
+fn sys_policy(options: &Options, rom: &Path) -> SysPolicy {
+    // an explicit --sys wins over the routines next to the rom
+    let routines = match (&options.sys_routines, options.sys) {
+        (Some(path), _) => Some(path.clone()),
+        (None, Some(_)) => None,
+        (None, None) => Some(rom.with_extension("sys")).filter(|p| p.exists()),
+    };
+    if let Some(path) = routines {
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| cannot_read(&path, &e))
+            .and_then(|text| {
+                Routines::parse(&text).map_err(|e| format!("{} : {}", path.display(), e))
+            });
+        match parsed {
+            Ok(routines) => return SysPolicy::Hle(routines),
+            Err(e) => fail(e),
         }
     }
+    match options.sys {
+        Some(Sys::Ignore) => SysPolicy::Ignore,
+        Some(Sys::Native) => SysPolicy::Native,
+        Some(Sys::Error) | None => SysPolicy::Error,
+    }
+}
+
+fn load(path: &Path) -> Program {
+    exit_on_error(path, Program::from_path(path))
+}
+
+fn exit_on_error<T>(path: &Path, result: Result<T, LoadError>) -> T {
+    result.unwrap_or_else(|e| match e {
+        LoadError::Io(e) => fail(cannot_read(path, &e)),
+        e => fail(format!("{} : {}", path.display(), e)),
+    })
+}
+
+// Tells what went wrong without the OS error codes
+fn cannot_read(path: &Path, e: &std::io::Error) -> String {
+    let reason = match e.kind() {
+        ErrorKind::NotFound => "no such file or directory".to_string(),
+        ErrorKind::PermissionDenied => "permission denied".to_string(),
+        ErrorKind::IsADirectory => "is a directory".to_string(),
+        _ => e.to_string(),
+    };
+    format!("cannot read {} : {}", path.display(), reason)
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
 }
@@ -1,6 +1,6 @@
-use std::ops::Index;
-use std::ops::IndexMut;
-use std::fmt;
+use core::ops::Index;
+use core::ops::IndexMut;
+use core::fmt;
 
 pub struct Screen {
     matrix: [bool; 64 * 32],
@@ -18,6 +18,12 @@ impl Screen {
     }
 }
 
+impl Default for Screen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Index<(usize, usize)> for Screen {
     type Output = bool;
 
@@ -42,8 +48,64 @@ impl fmt::Display for Screen {
                     write!(f, " ")?
                 }
             }
-            write!(f, "\n")?
+            writeln!(f)?
+        }
+        writeln!(f)
+    }
+}
+
+// Two pixels per character, one above the other, with the half block characters
+pub struct HalfBlocks<'a>(&'a Screen);
+
+// Eight pixels per character, two wide and four high, with the braille patterns
+pub struct Braille<'a>(&'a Screen);
+
+impl Screen {
+    pub fn half_blocks(&self) -> HalfBlocks<'_> {
+        HalfBlocks(self)
+    }
+
+    pub fn braille(&self) -> Braille<'_> {
+        Braille(self)
+    }
+}
+
+impl fmt::Display for HalfBlocks<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in (0..32).step_by(2) {
+            for x in 0..64 {
+                let c = match (self.0[(y, x)], self.0[(y + 1, x)]) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
+                };
+                write!(f, "{}", c)?
+            }
+            writeln!(f)?
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Braille<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the dot of each pixel of the 2x4 cell, by row
+        const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+        for y in (0..32).step_by(4) {
+            for x in (0..64).step_by(2) {
+                let mut bits = 0;
+                for (dy, row) in DOTS.iter().enumerate() {
+                    for (dx, dot) in row.iter().enumerate() {
+                        if self.0[(y + dy, x + dx)] {
+                            bits |= dot;
+                        }
+                    }
+                }
+                write!(f, "{}", char::from_u32(0x2800 + bits).unwrap())?
+            }
+            writeln!(f)?
         }
-        write!(f, "\n")
+        Ok(())
     }
 }
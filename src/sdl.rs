@@ -0,0 +1,383 @@
+use chip8::chip8::database::Entry;
+use chip8::chip8::font::glyph;
+use chip8::chip8::launcher::Launcher;
+use chip8::chip8::machine::{AudioSink, Clock, Exit, FixedRate, InputSource, VideoSink, AZERTY};
+use chip8::screen::Screen;
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+use sdl2::EventPump;
+
+// The size of the window the launcher is laid out for, scaled to the window
+const LAUNCHER_SCALE: u32 = 10;
+
+// The key bound to the keycode, or the key of the keymap with its name
+fn key(keymap: &[char; 16], bindings: &[(Keycode, usize)], keycode: Keycode) -> Option<usize> {
+    if let Some(&(_, key)) = bindings.iter().find(|(k, _)| *k == keycode) {
+        return Some(key);
+    }
+    let name = keycode.name();
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => keymap.iter().position(|k| k.eq_ignore_ascii_case(&c)),
+        _ => None,
+    }
+}
+
+struct SquareWave {
+    phase: f32,
+    step: f32,
+    volume: f32,
+}
+
+impl AudioCallback for SquareWave {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = if self.phase < 0.5 {
+                self.volume
+            } else {
+                -self.volume
+            };
+            self.phase = (self.phase + self.step) % 1.0;
+        }
+    }
+}
+
+// A window with the screen scaled `scale` times, a 440 Hz buzzer and the
+// keyboard, at the given frame rate
+pub struct Sdl {
+    pub foreground: Color,
+    pub background: Color,
+    // more keys bound to the keypad, the arrows of a game for example
+    pub bindings: Vec<(Keycode, usize)>,
+    // Backspace goes back to the launcher
+    pub launcher: bool,
+    // the keys bound to the keypad 0 to F
+    pub keymap: [char; 16],
+    // the size of a CHIP-8 pixel
+    scale: u32,
+    title: String,
+    exit: Exit,
+    canvas: Canvas<Window>,
+    events: EventPump,
+    // None when the audio device cannot be opened
+    audio: Option<AudioDevice<SquareWave>>,
+    clock: FixedRate,
+}
+
+impl Sdl {
+    pub fn new(title: &str, fps: u32, scale: u32) -> Self {
+        let sdl_context = sdl2::init().unwrap();
+        let video_subsystem = sdl_context.video().unwrap();
+
+        let window = video_subsystem
+            .window(title, 64 * scale, 32 * scale)
+            .position_centered()
+            .build()
+            .unwrap();
+
+        let mut canvas = window.into_canvas().build().unwrap();
+        canvas.clear();
+        canvas.present();
+
+        let desired = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(1),
+            samples: None,
+        };
+        let audio = sdl_context.audio().ok().and_then(|audio| {
+            audio
+                .open_playback(None, &desired, |spec| SquareWave {
+                    phase: 0.0,
+                    step: 440.0 / spec.freq as f32,
+                    volume: 0.25,
+                })
+                .ok()
+        });
+
+        Self {
+            foreground: Color::BLACK,
+            background: Color::WHITE,
+            bindings: Vec::new(),
+            launcher: false,
+            keymap: AZERTY,
+            scale,
+            title: title.to_string(),
+            exit: Exit::Quit,
+            canvas,
+            events: sdl_context.event_pump().unwrap(),
+            audio,
+            clock: FixedRate::new(fps),
+        }
+    }
+}
+
+impl Sdl {
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_string();
+        self.canvas.window_mut().set_title(title).unwrap();
+    }
+
+    // The colours and the keys of a game of the database, the arrows, space
+    // for a and return for b, or the default ones
+    pub fn apply(&mut self, entry: Option<&Entry>) {
+        self.foreground = Color::BLACK;
+        self.background = Color::WHITE;
+        self.bindings.clear();
+        let Some(entry) = entry else {
+            return;
+        };
+        if let Some(colors) = &entry.colors {
+            if let [background, foreground, ..] = colors.pixels[..] {
+                self.background = Color::RGB(background[0], background[1], background[2]);
+                self.foreground = Color::RGB(foreground[0], foreground[1], foreground[2]);
+            }
+        }
+        for (name, &key) in &entry.keys {
+            let keycode = match name.as_str() {
+                "up" => Keycode::Up,
+                "down" => Keycode::Down,
+                "left" => Keycode::Left,
+                "right" => Keycode::Right,
+                "a" => Keycode::Space,
+                "b" => Keycode::Return,
+                _ => continue,
+            };
+            self.bindings.push((keycode, key as usize & 0xF));
+        }
+    }
+}
+
+// The launcher : the titles on the left, the thumbnail of the selected rom on
+// the right, the text in the CHIP-8 font with dots of DOT pixels
+const DOT: i32 = 2;
+// a character and the space after it
+const CHAR_WIDTH: i32 = 5 * DOT;
+const LINE_HEIGHT: i32 = 6 * DOT;
+const LIST_COLUMNS: usize = 30;
+const LIST_ROWS: usize = 22;
+const THUMBNAIL_SCALE: u32 = 4;
+const HELP: &str = "UP DOWN SELECT  ENTER PLAY  BACKSPACE BACK  ESC QUIT";
+
+impl Sdl {
+    fn text(&mut self, text: &str, x: i32, y: i32) {
+        for (i, c) in text.chars().enumerate() {
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for column in 0..4 {
+                    if bits & (0x80 >> column) != 0 {
+                        let dot_x = x + i as i32 * CHAR_WIDTH + column * DOT;
+                        let dot_y = y + row as i32 * DOT;
+                        let dot = Rect::new(dot_x, dot_y, DOT as u32, DOT as u32);
+                        self.canvas.fill_rect(dot).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw_launcher(&mut self, launcher: &Launcher, selected: usize) {
+        self.canvas.set_draw_color(Color::BLACK);
+        self.canvas.clear();
+
+        let first = launcher.first_row(selected, LIST_ROWS);
+        let last = launcher.len().min(first + LIST_ROWS);
+        for i in first..last {
+            let title: String = launcher.titles[i].chars().take(LIST_COLUMNS).collect();
+            let y = 10 + (i - first) as i32 * LINE_HEIGHT;
+            if i == selected {
+                let width = (LIST_COLUMNS as i32 * CHAR_WIDTH + DOT) as u32;
+                self.canvas.set_draw_color(Color::WHITE);
+                let bar = Rect::new(10 - DOT, y - DOT, width, LINE_HEIGHT as u32);
+                self.canvas.fill_rect(bar).unwrap();
+                self.canvas.set_draw_color(Color::BLACK);
+            } else {
+                self.canvas.set_draw_color(Color::WHITE);
+            }
+            self.text(&title, 10, y);
+        }
+
+        // the thumbnail in a frame, with the file name and the position below
+        let (x, y) = (330, 10);
+        let (width, height) = (64 * THUMBNAIL_SCALE, 32 * THUMBNAIL_SCALE);
+        self.canvas.set_draw_color(Color::WHITE);
+        let frame = Rect::new(x - 2, y - 2, width + 4, height + 4);
+        self.canvas.draw_rect(frame).unwrap();
+        let screen = &launcher.thumbnails[selected];
+        for row in 0..32 {
+            for column in 0..64 {
+                if screen[(row, column)] {
+                    let pixel = Rect::new(
+                        x + (column as u32 * THUMBNAIL_SCALE) as i32,
+                        y + (row as u32 * THUMBNAIL_SCALE) as i32,
+                        THUMBNAIL_SCALE,
+                        THUMBNAIL_SCALE,
+                    );
+                    self.canvas.fill_rect(pixel).unwrap();
+                }
+            }
+        }
+        let name: String = launcher.roms[selected].name.chars().take(28).collect();
+        let y = y + height as i32 + 10;
+        self.text(&name, x, y);
+        let position = format!("{}/{}", selected + 1, launcher.len());
+        self.text(&position, x, y + LINE_HEIGHT);
+        self.text(HELP, 10, (32 * LAUNCHER_SCALE) as i32 - LINE_HEIGHT);
+        self.canvas.present();
+    }
+
+    // Shows the launcher until a rom is chosen, None when the user quits
+    pub fn pick(&mut self, launcher: &Launcher, selected: usize) -> Option<usize> {
+        let factor = self.scale as f32 / LAUNCHER_SCALE as f32;
+        self.canvas.set_scale(factor, factor).unwrap();
+        let picked = self.choose(launcher, selected);
+        self.canvas.set_scale(1.0, 1.0).unwrap();
+        picked
+    }
+
+    fn choose(&mut self, launcher: &Launcher, mut selected: usize) -> Option<usize> {
+        self.set_title("chip8");
+        self.set_tone(false);
+        let last = launcher.len() - 1;
+        loop {
+            let events: Vec<Event> = self.events.poll_iter().collect();
+            for event in events {
+                let keycode = match event {
+                    Event::Quit { .. } => return None,
+                    Event::KeyDown {
+                        keycode: Some(keycode),
+                        ..
+                    } => keycode,
+                    _ => continue,
+                };
+                selected = match keycode {
+                    Keycode::Escape => return None,
+                    Keycode::Return | Keycode::KpEnter => return Some(selected),
+                    Keycode::Up => selected.saturating_sub(1),
+                    Keycode::Down => (selected + 1).min(last),
+                    Keycode::PageUp => selected.saturating_sub(LIST_ROWS),
+                    Keycode::PageDown => (selected + LIST_ROWS).min(last),
+                    Keycode::Home => 0,
+                    Keycode::End => last,
+                    _ => selected,
+                };
+            }
+            self.draw_launcher(launcher, selected);
+            self.clock.wait_frame();
+        }
+    }
+}
+
+impl VideoSink for Sdl {
+    fn present(&mut self, screen: &Screen) {
+        self.canvas.clear();
+        for y in 0..32 {
+            for x in 0..64 {
+                if screen[(y, x)] {
+                    self.canvas.set_draw_color(self.foreground);
+                } else {
+                    self.canvas.set_draw_color(self.background);
+                }
+                let scale = self.scale;
+                let (x, y) = (x as u32 * scale, y as u32 * scale);
+                self.canvas
+                    .fill_rect(Rect::new(x as i32, y as i32, scale, scale))
+                    .unwrap();
+            }
+        }
+        self.canvas.present();
+    }
+
+    fn waiting_for_key(&mut self, waiting: bool) {
+        let title = if waiting {
+            format!("{} (waiting for a key)", self.title)
+        } else {
+            self.title.clone()
+        };
+        self.canvas.window_mut().set_title(&title).unwrap();
+    }
+}
+
+impl InputSource for Sdl {
+    fn poll(&mut self, keys: &mut [bool; 16]) -> bool {
+        for event in self.events.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => {
+                    self.exit = Exit::Quit;
+                    return false;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } if self.launcher => {
+                    self.exit = Exit::Menu;
+                    return false;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::PageDown),
+                    ..
+                } => {
+                    self.exit = Exit::Next;
+                    return false;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::PageUp),
+                    ..
+                } => {
+                    self.exit = Exit::Previous;
+                    return false;
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(k) = key(&self.keymap, &self.bindings, keycode) {
+                        keys[k] = true;
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    if let Some(k) = key(&self.keymap, &self.bindings, keycode) {
+                        keys[k] = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+        true
+    }
+
+    fn exit(&mut self) -> Exit {
+        self.exit
+    }
+}
+
+impl AudioSink for Sdl {
+    fn set_tone(&mut self, on: bool) {
+        if let Some(device) = &self.audio {
+            if on {
+                device.resume();
+            } else {
+                device.pause();
+            }
+        }
+    }
+}
+
+impl Clock for Sdl {
+    fn wait_frame(&mut self) {
+        self.clock.wait_frame()
+    }
+}
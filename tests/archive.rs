@@ -0,0 +1,87 @@
+use chip8::chip8::archive;
+use chip8::chip8::program::LoadError;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Cursor, Write};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        if name.ends_with('/') {
+            zip.add_directory(*name, FileOptions::default()).unwrap();
+        } else {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn reads_plain_roms() {
+    let roms = archive::read("pong.ch8", &[0x12, 0x00]).unwrap();
+    assert_eq!(roms.len(), 1);
+    assert_eq!(roms[0].name, "pong.ch8");
+    assert_eq!(roms[0].program.content, [0x12, 0x00]);
+}
+
+#[test]
+fn reads_every_rom_of_a_zip() {
+    let bytes = zip(&[
+        ("games/", b""),
+        ("games/pong.ch8", &[0x12, 0x00]),
+        ("games/tank.ch8", &[0x60, 0x01]),
+        ("big.bin", &[0; 5000]),
+    ]);
+    let roms = archive::read("games.zip", &bytes).unwrap();
+    let names: Vec<&str> = roms.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["games/pong.ch8", "games/tank.ch8"]);
+    assert_eq!(roms[1].program.content, [0x60, 0x01]);
+
+    let empty = zip(&[("big.bin", &[0; 5000])]);
+    assert!(matches!(
+        archive::read("big.zip", &empty),
+        Err(LoadError::NoRom)
+    ));
+}
+
+#[test]
+fn reads_gzip() {
+    let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+    gzip.write_all(&[0x00, 0xE0]).unwrap();
+    let roms = archive::read("clear.ch8.gz", &gzip.finish().unwrap()).unwrap();
+    assert_eq!(roms[0].name, "clear.ch8");
+    assert_eq!(roms[0].program.content, [0x00, 0xE0]);
+
+    let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+    gzip.write_all(&[0; 100000]).unwrap();
+    let bomb = archive::read("big.gz", &gzip.finish().unwrap());
+    assert!(matches!(bomb, Err(LoadError::TooLarge { .. })));
+}
+
+#[test]
+fn opens_directories() {
+    let dir = std::env::temp_dir().join(format!("chip8-archive-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("b.ch8"), [0x12, 0x00]).unwrap();
+    std::fs::write(dir.join("a.zip"), zip(&[("x.ch8", &[0x00, 0xE0])])).unwrap();
+    std::fs::write(dir.join("huge.bin"), [0; 5000]).unwrap();
+    std::fs::write(dir.join(".hidden"), [0]).unwrap();
+    std::fs::write(dir.join("C.C8"), [0x12, 0x00]).unwrap();
+    // not roms
+    std::fs::write(dir.join("notes.txt"), "12 00").unwrap();
+    std::fs::write(dir.join("README"), [0x12, 0x00]).unwrap();
+
+    let roms = archive::open(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let names: Vec<&str> = roms.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["C.C8", "x.ch8", "b.ch8"]);
+}
+
+#[test]
+fn reports_missing_files() {
+    let error = archive::open("tests/roms/missing.zip").err().unwrap();
+    assert!(matches!(error, LoadError::Io(_)));
+}
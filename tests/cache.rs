@@ -0,0 +1,65 @@
+use chip8::chip8::cpu::Cpu;
+use chip8::chip8::interpreter::{Error, Interpreter};
+use chip8::chip8::program::Program;
+use chip8::chip8::sys::{Routines, SysPolicy};
+
+fn run(interpreter: &mut Interpreter, program: &[u8], steps: usize) {
+    interpreter.load_program(&Program::from(program.to_vec())).unwrap();
+    for _ in 0..steps {
+        interpreter.try_step().unwrap();
+    }
+}
+
+#[test]
+fn writes_into_code_invalidate_the_cache() {
+    let program = [
+        0x73, 0x01, // 200  ADD V3, 1
+        0x60, 0x70, // 202  LD V0, 70
+        0x61, 0x05, // 204  LD V1, 5
+        0xA2, 0x00, // 206  LD I, 200
+        0xF1, 0x55, // 208  LD [I], V1 : 200 becomes ADD V0, 5
+        0x12, 0x00, // 20A  JP 200
+    ];
+    let mut interpreter = Interpreter::with_cache();
+    run(&mut interpreter, &program, 7);
+    assert_eq!(interpreter.cpu.registers[0], 0x75);
+    assert_eq!(interpreter.cpu.registers[3], 1);
+}
+
+// Rewrites the instruction at 200 into ADD V0, 5
+fn patch(cpu: &mut Cpu) {
+    cpu.memory[0x200..0x202].copy_from_slice(&[0x70, 0x05]);
+}
+
+#[test]
+fn sys_routines_invalidate_the_cache() {
+    let program = [
+        0x70, 0x01, // 200  ADD V0, 1
+        0x03, 0x00, // 202  SYS 300
+        0x12, 0x00, // 204  JP 200
+    ];
+    let mut routines = Routines::new();
+    routines.register(0x300, patch);
+    let mut interpreter = Interpreter::with_cache();
+    interpreter.sys = SysPolicy::Hle(routines);
+    run(&mut interpreter, &program, 4);
+    assert_eq!(interpreter.cpu.registers[0], 6);
+}
+
+#[test]
+fn try_step_runs_from_the_cache() {
+    let program = [
+        0x70, 0x01, // 200  ADD V0, 1
+        0x12, 0x00, // 202  JP 200
+    ];
+    let mut interpreter = Interpreter::with_cache();
+    run(&mut interpreter, &program, 2);
+    // a write from outside without flush_cache keeps the decoded instruction
+    interpreter.cpu.memory[0x200..0x202].copy_from_slice(&[0xFF, 0xFF]);
+    interpreter.try_step().unwrap();
+    assert_eq!(interpreter.cpu.registers[0], 2);
+
+    interpreter.try_step().unwrap();
+    interpreter.flush_cache();
+    assert_eq!(interpreter.try_step(), Err(Error::UnknownOpcode(0xFFFF)));
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use chip8::chip8::cdp1802::{Cdp1802, Fault, VARIABLES};
+use chip8::chip8::interpreter::Error;
+use chip8::chip8::sys::SysPolicy;
+use common::CpuBuilder;
+
+// Runs `code` loaded at 0 of a 64K memory until P becomes 4
+fn run(code: &[u8]) -> (Cdp1802, Vec<u8>) {
+    let mut memory = vec![0; 0x10000];
+    memory[..code.len()].copy_from_slice(code);
+    let mut core = Cdp1802::new();
+    while core.p != 4 {
+        core.step(&mut memory).unwrap();
+    }
+    (core, memory)
+}
+
+#[test]
+fn arithmetic_sets_df() {
+    // LDI F0, ADI 20
+    let (core, _) = run(&[0xF8, 0xF0, 0xFC, 0x20, 0xD4]);
+    assert_eq!((core.d, core.df), (0x10, true));
+    // LDI 10, SMI 20 borrows
+    let (core, _) = run(&[0xF8, 0x10, 0xFF, 0x20, 0xD4]);
+    assert_eq!((core.d, core.df), (0xF0, false));
+    // LDI 81, SHRC with DF clear, then RSHL
+    let (core, _) = run(&[0xF8, 0x81, 0x76, 0x7E, 0xD4]);
+    assert_eq!((core.d, core.df), (0x81, false));
+}
+
+#[test]
+fn registers_and_memory() {
+    // LDI 12, PHI R7, LDI 34, PLO R7, LDI AB, STR R7, LDA R7
+    let (core, memory) = run(&[
+        0xF8, 0x12, 0xB7, 0xF8, 0x34, 0xA7, 0xF8, 0xAB, 0x57, 0x47, 0xD4,
+    ]);
+    assert_eq!(memory[0x1234], 0xAB);
+    assert_eq!(core.r[7], 0x1235);
+}
+
+#[test]
+fn branches() {
+    // LDI 0, BZ 06, LDI 1, SEP 4, LBR 000A, then SEP 4
+    let (core, _) = run(&[
+        0xF8, 0x00, 0x32, 0x06, 0xF8, 0x01, 0xC0, 0x00, 0x0A, 0xD4, 0xD4,
+    ]);
+    assert_eq!(core.d, 0);
+    assert_eq!(core.r[0], 0x0B);
+    // LSKP over LDI 1
+    let (core, _) = run(&[0xC8, 0xF8, 0x01, 0xD4]);
+    assert_eq!(core.d, 0);
+}
+
+#[test]
+fn routines_see_the_chip8_state() {
+    // V3 += 5 : LDI F3, PLO R6, LDN R6, ADI 05, STR R6, SEP 4
+    CpuBuilder::new()
+        .sys(SysPolicy::Native)
+        .register(3, 0x10)
+        .memory(0x300, &[0xF8, 0xF3, 0xA6, 0x06, 0xFC, 0x05, 0x56, 0xD4])
+        .execute(0x0300)
+        .register(3, 0x15)
+        .pc(0x202);
+
+    // first byte of the display buffer : LDI FF, STR RB, SEP 4
+    CpuBuilder::new()
+        .sys(SysPolicy::Native)
+        .memory(0x300, &[0xF8, 0xFF, 0x5B, 0xD4])
+        .execute(0x0300)
+        .pixel(0, 0, true)
+        .pixel(7, 0, true)
+        .pixel(8, 0, false)
+        .pixels_on(8);
+
+    // RA is I : LDI 34, PLO RA, SEP 4
+    CpuBuilder::new()
+        .sys(SysPolicy::Native)
+        .index(0x250)
+        .memory(0x300, &[0xF8, 0x34, 0xAA, 0xD4])
+        .execute(0x0300)
+        .index(0x234);
+}
+
+#[test]
+fn routines_can_skip_chip8_instructions() {
+    // R5 is the CHIP-8 PC : INC R5, INC R5, SEP 4
+    CpuBuilder::new()
+        .sys(SysPolicy::Native)
+        .memory(0x300, &[0x15, 0x15, 0xD4])
+        .execute(0x0300)
+        .pc(0x204);
+}
+
+#[test]
+fn hybrid_program() {
+    // V0 = 7, SYS 300 doubles it, V1 = V0
+    let mut interpreter = CpuBuilder::new()
+        .sys(SysPolicy::Native)
+        .memory(0x200, &[0x60, 0x07, 0x03, 0x00, 0x81, 0x00])
+        // LDI F0, PLO R6, LDN R6, SHL, STR R6, SEP 4
+        .memory(0x300, &[0xF8, 0xF0, 0xA6, 0x06, 0xFE, 0x56, 0xD4])
+        .build();
+    for _ in 0..3 {
+        interpreter.try_step().unwrap();
+    }
+    assert_eq!(interpreter.cpu.registers[1], 14);
+    assert_eq!(interpreter.cpu.memory[VARIABLES], 14);
+}
+
+#[test]
+fn faults_are_errors() {
+    let mut interpreter = CpuBuilder::new()
+        .sys(SysPolicy::Native)
+        .memory(0x200, &[0x03, 0x00])
+        .build();
+    assert_eq!(
+        interpreter.try_step(),
+        Err(Error::Native(0x300, Fault::Idle))
+    );
+
+    // BR 00 never returns
+    let mut interpreter = CpuBuilder::new()
+        .sys(SysPolicy::Native)
+        .memory(0x200, &[0x03, 0x00])
+        .memory(0x300, &[0x30, 0x00])
+        .build();
+    assert_eq!(
+        interpreter.try_step(),
+        Err(Error::Native(0x300, Fault::Timeout))
+    );
+}
@@ -0,0 +1,144 @@
+use chip8::chip8::cfg::{Cfg, Edge, EdgeKind, Flow};
+use chip8::chip8::program::Program;
+
+// A skip over a jump, a call and an endless loop, 20C is never reached
+const BRANCHES: [u8; 20] = [
+    0x60, 0x05, // 200  LD V0, 5
+    0x30, 0x05, // 202  SE V0, 5
+    0x12, 0x08, // 204  JP 208
+    0x00, 0xE0, // 206  CLS
+    0x22, 0x0E, // 208  CALL 20E
+    0x12, 0x0A, // 20A  JP 20A
+    0x00, 0xE0, // 20C  CLS
+    0x70, 0x01, // 20E  ADD V0, 1
+    0x00, 0xEE, // 210  RET
+    0x00, 0x00,
+];
+
+fn edge(target: u16, kind: EdgeKind) -> Edge {
+    Edge { target, kind }
+}
+
+fn starts(cfg: &Cfg) -> Vec<u16> {
+    cfg.blocks.keys().copied().collect()
+}
+
+#[test]
+fn cuts_blocks_at_skips_jumps_and_calls() {
+    let cfg = Cfg::build(&Program::from(BRANCHES.to_vec()));
+    assert_eq!(starts(&cfg), [0x200, 0x204, 0x206, 0x208, 0x20A, 0x20E]);
+
+    let block = &cfg.blocks[&0x200];
+    assert_eq!(block.instructions, [(0x200, 0x6005), (0x202, 0x3005)]);
+    assert_eq!(block.terminator, Flow::Skip);
+    assert_eq!(
+        block.successors,
+        [
+            edge(0x204, EdgeKind::Fallthrough),
+            edge(0x206, EdgeKind::Skip)
+        ]
+    );
+    assert_eq!(cfg.blocks[&0x204].successors, [edge(0x208, EdgeKind::Jump)]);
+    // 206 falls into the call since 208 is a leader
+    assert_eq!(
+        cfg.blocks[&0x206].successors,
+        [edge(0x208, EdgeKind::Fallthrough)]
+    );
+    assert_eq!(
+        cfg.blocks[&0x208].successors,
+        [
+            edge(0x20E, EdgeKind::Call),
+            edge(0x20A, EdgeKind::Fallthrough)
+        ]
+    );
+    assert_eq!(cfg.blocks[&0x20A].successors, [edge(0x20A, EdgeKind::Jump)]);
+
+    let subroutine = &cfg.blocks[&0x20E];
+    assert_eq!(subroutine.end(), 0x212);
+    assert_eq!(subroutine.terminator, Flow::Return);
+    assert!(subroutine.successors.is_empty());
+}
+
+#[test]
+fn groups_blocks_by_subroutine() {
+    let cfg = Cfg::build(&Program::from(BRANCHES.to_vec()));
+    assert_eq!(
+        cfg.subroutines.keys().copied().collect::<Vec<_>>(),
+        [0x200, 0x20E]
+    );
+    // calls are not followed
+    assert_eq!(cfg.subroutines[&0x200], [0x200, 0x204, 0x206, 0x208, 0x20A]);
+    assert_eq!(cfg.subroutines[&0x20E], [0x20E]);
+}
+
+#[test]
+fn marks_jp_v0_indirect() {
+    // LD V0, 2 then JP V0, 300
+    let cfg = Cfg::build(&Program::from(vec![0x60, 0x02, 0xB3, 0x00]));
+    assert_eq!(starts(&cfg), [0x200]);
+    let block = &cfg.blocks[&0x200];
+    assert_eq!(block.terminator, Flow::Indirect(0x300));
+    assert!(block.is_indirect());
+    assert!(block.successors.is_empty());
+
+    assert!(cfg.to_dot(None).contains(
+        "b_200 [label=\"200: LD V0, 2\\l202: JP V0, 300\\l\", color=red, xlabel=\"indirect\"];"
+    ));
+}
+
+#[test]
+fn exports_dot() {
+    let cfg = Cfg::build(&Program::from(BRANCHES.to_vec()));
+
+    let dot = cfg.to_dot(None);
+    assert!(dot.starts_with("digraph cfg {\n"));
+    assert!(dot.contains("    subgraph cluster_200 {\n        label=\"sub_200\";\n"));
+    assert!(dot.contains("    subgraph cluster_20E {\n"));
+    assert!(dot.contains("        b_20E [label=\"20E: ADD V0, 1\\l210: RET\\l\"];\n"));
+    assert!(dot.contains("    b_200 -> b_206 [label=\"skip\", style=bold, color=darkgreen];\n"));
+    assert!(dot.contains("    b_204 -> b_208 [label=\"jump\", color=blue];\n"));
+    assert!(dot.contains("    b_208 -> b_20E [label=\"call\", style=dashed];\n"));
+    assert!(!dot.contains("20C"));
+
+    // a single subroutine draws its callees as stubs
+    let dot = cfg.to_dot(Some(0x200));
+    assert!(!dot.contains("subgraph"));
+    assert!(!dot.contains("    b_20E ["));
+    assert!(dot.contains("    b_208 -> sub_20E [label=\"call\", style=dashed];\n"));
+    assert!(dot.contains("    sub_20E [shape=ellipse];\n"));
+}
+
+#[test]
+fn exports_json() {
+    let cfg = Cfg::build(&Program::from(BRANCHES.to_vec()));
+
+    let json: serde_json::Value = serde_json::from_str(&cfg.to_json(None)).unwrap();
+    assert_eq!(json["entry"], 0x200);
+    assert_eq!(json["subroutines"][1]["entry"], 0x20E);
+    assert_eq!(json["subroutines"][1]["blocks"], serde_json::json!([0x20E]));
+    let blocks = json["blocks"].as_array().unwrap();
+    assert_eq!(blocks.len(), 6);
+    assert_eq!(
+        blocks[0],
+        serde_json::json!({
+            "start": 0x200,
+            "end": 0x204,
+            "indirect": false,
+            "instructions": [
+                {"addr": 0x200, "op": 0x6005, "text": "LD V0, 5"},
+                {"addr": 0x202, "op": 0x3005, "text": "SE V0, 5"},
+            ],
+            "successors": [
+                {"target": 0x204, "kind": "fallthrough"},
+                {"target": 0x206, "kind": "skip"},
+            ],
+        })
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&cfg.to_json(Some(0x20E))).unwrap();
+    assert_eq!(json["subroutines"].as_array().unwrap().len(), 1);
+    let blocks = json["blocks"].as_array().unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0]["start"], 0x20E);
+    assert_eq!(blocks[0]["instructions"][1]["text"], "RET");
+}
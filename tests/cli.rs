@@ -0,0 +1,86 @@
+#![cfg(feature = "std")]
+
+use std::process::{Command, Output};
+
+// JP FFF, the last byte of memory cannot hold an instruction
+const END_OF_MEMORY: [u8; 2] = [0x1F, 0xFF];
+
+// Runs the chip8 binary on a rom written into a temporary directory
+fn chip8(args: &[&str], name: &str, rom: &[u8]) -> Output {
+    chip8_with(args, &[(name, rom)])
+}
+
+// The same with other files next to the rom, which comes first
+fn chip8_with(args: &[&str], files: &[(&str, &[u8])]) -> Output {
+    let dir = std::env::temp_dir().join(format!("chip8-cli-{}-{}", std::process::id(), files[0].0));
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, content) in files {
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_chip8"))
+        .current_dir(&dir)
+        .args(args)
+        .arg(dir.join(files[0].0))
+        .arg("--no-database")
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn trace_reports_the_end_of_memory() {
+    let output = chip8(&["trace"], "trace.ch8", &END_OF_MEMORY);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("200  1FFF  JP FFF"));
+    assert_eq!(
+        stderr(&output),
+        "error: FFF  program counter FFF out of memory\n"
+    );
+}
+
+#[test]
+fn bench_reports_the_end_of_memory() {
+    let output = chip8(&["bench", "--frames", "10"], "bench.ch8", &END_OF_MEMORY);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).ends_with("bench.ch8 : program counter FFF out of memory\n"));
+
+    let output = chip8(&["bench", "--frames", "10"], "loop.ch8", &[0x12, 0x00]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn an_explicit_sys_wins_over_the_sys_file() {
+    // SYS 300 then an endless loop, the .sys file emulates 300
+    let rom: &[u8] = &[0x03, 0x00, 0x12, 0x02];
+    let files: [(&str, &[u8]); 2] = [("sys.ch8", rom), ("sys.sys", b"300 nop\n")];
+    let bench = ["bench", "--frames", "10"];
+
+    let output = chip8_with(&bench, &files);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = chip8_with(&[&bench[..], &["--sys", "error"]].concat(), &files);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("300"), "{}", stderr(&output));
+
+    let output = chip8_with(&[&bench[..], &["--sys", "ignore"]].concat(), &files);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // the routines given on the command line conflict with it
+    let args = [
+        &bench[..],
+        &["--sys", "ignore", "--sys-routines", "sys.sys"],
+    ]
+    .concat();
+    let output = chip8_with(&args, &files);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("cannot be used with"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,212 @@
+// Helpers shared by the integration tests : building a cpu state, executing
+// one opcode on it and checking the state afterwards
+#![allow(dead_code)]
+
+use chip8::chip8::cpu::Cpu;
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::platform::{Platform, Quirks};
+use chip8::chip8::sys::SysPolicy;
+
+pub struct CpuBuilder {
+    cpu: Cpu,
+    quirks: Quirks,
+    sys: SysPolicy,
+}
+
+impl CpuBuilder {
+    pub fn new() -> Self {
+        Self {
+            cpu: Cpu::new(),
+            quirks: Platform::Chip8.quirks(),
+            sys: SysPolicy::Error,
+        }
+    }
+
+    pub fn register(mut self, x: usize, value: u8) -> Self {
+        self.cpu.registers[x] = value;
+        self
+    }
+
+    pub fn index(mut self, index: u16) -> Self {
+        self.cpu.index = index;
+        self
+    }
+
+    pub fn pc(mut self, pc: u16) -> Self {
+        self.cpu.program_counter = pc;
+        self
+    }
+
+    // Return addresses from the outermost call to the innermost one
+    pub fn stack(mut self, addrs: &[u16]) -> Self {
+        for addr in addrs {
+            self.cpu.stack_pointer += 1;
+            self.cpu.stack[self.cpu.stack_pointer] = *addr;
+        }
+        self
+    }
+
+    pub fn memory(mut self, addr: u16, bytes: &[u8]) -> Self {
+        let addr = addr as usize;
+        self.cpu.memory[addr..addr + bytes.len()].copy_from_slice(bytes);
+        self
+    }
+
+    pub fn key(mut self, key: usize) -> Self {
+        self.cpu.keys[key] = true;
+        self
+    }
+
+    pub fn delay_timer(mut self, value: u8) -> Self {
+        self.cpu.delay_timer = value;
+        self
+    }
+
+    pub fn sound_timer(mut self, value: u8) -> Self {
+        self.cpu.sound_timer = value;
+        self
+    }
+
+    pub fn pixel(mut self, x: usize, y: usize) -> Self {
+        self.cpu.display[(y, x)] = true;
+        self
+    }
+
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    pub fn sys(mut self, sys: SysPolicy) -> Self {
+        self.sys = sys;
+        self
+    }
+
+    pub fn build(self) -> Interpreter {
+        let mut interpreter = Interpreter::new();
+        interpreter.cpu = self.cpu;
+        interpreter.quirks = self.quirks;
+        interpreter.sys = self.sys;
+        interpreter
+    }
+
+    // Writes the opcode at the program counter and executes it
+    pub fn execute(self, op: u16) -> Expect {
+        let mut interpreter = self.build();
+        execute(&mut interpreter, op);
+        Expect { interpreter, op }
+    }
+}
+
+impl Default for CpuBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn execute(interpreter: &mut Interpreter, op: u16) {
+    let pc = interpreter.cpu.program_counter as usize;
+    interpreter.cpu.memory[pc..pc + 2].copy_from_slice(&op.to_be_bytes());
+    interpreter.flush_cache();
+    interpreter.step();
+}
+
+// Assertions on the state after an opcode, each one names the opcode when it fails
+pub struct Expect {
+    pub interpreter: Interpreter,
+    pub op: u16,
+}
+
+impl Expect {
+    pub fn register(self, x: usize, value: u8) -> Self {
+        assert_eq!(
+            self.interpreter.cpu.registers[x], value,
+            "{:04X} : V{:X}",
+            self.op, x
+        );
+        self
+    }
+
+    pub fn flag(self, value: u8) -> Self {
+        self.register(0xF, value)
+    }
+
+    pub fn index(self, index: u16) -> Self {
+        assert_eq!(self.interpreter.cpu.index, index, "{:04X} : I", self.op);
+        self
+    }
+
+    pub fn pc(self, pc: u16) -> Self {
+        assert_eq!(
+            self.interpreter.cpu.program_counter, pc,
+            "{:04X} : PC",
+            self.op
+        );
+        self
+    }
+
+    pub fn stack(self, addrs: &[u16]) -> Self {
+        let cpu = &self.interpreter.cpu;
+        assert_eq!(
+            &cpu.stack[1..=cpu.stack_pointer],
+            addrs,
+            "{:04X} : stack",
+            self.op
+        );
+        self
+    }
+
+    pub fn memory(self, addr: u16, bytes: &[u8]) -> Self {
+        let addr = addr as usize;
+        assert_eq!(
+            &self.interpreter.cpu.memory[addr..addr + bytes.len()],
+            bytes,
+            "{:04X} : memory at {:03X}",
+            self.op,
+            addr
+        );
+        self
+    }
+
+    pub fn delay_timer(self, value: u8) -> Self {
+        assert_eq!(
+            self.interpreter.cpu.delay_timer, value,
+            "{:04X} : DT",
+            self.op
+        );
+        self
+    }
+
+    pub fn sound_timer(self, value: u8) -> Self {
+        assert_eq!(
+            self.interpreter.cpu.sound_timer, value,
+            "{:04X} : ST",
+            self.op
+        );
+        self
+    }
+
+    pub fn pixel(self, x: usize, y: usize, on: bool) -> Self {
+        assert_eq!(
+            self.interpreter.cpu.display[(y, x)],
+            on,
+            "{:04X} : pixel ({}, {})",
+            self.op,
+            x,
+            y
+        );
+        self
+    }
+
+    pub fn pixels_on(self, count: usize) -> Self {
+        let on = self
+            .interpreter
+            .cpu
+            .display
+            .to_string()
+            .matches('X')
+            .count();
+        assert_eq!(on, count, "{:04X} : pixels on", self.op);
+        self
+    }
+}
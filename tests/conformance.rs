@@ -0,0 +1,258 @@
+use chip8::chip8::instructions::Instruction;
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::machine::{Headless, Machine};
+use chip8::chip8::platform::{Platform, Quirks};
+use chip8::chip8::program::Program;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Instructions per frame, enough for the test suite menus to settle quickly
+const TICKRATE: u32 = 500;
+
+struct Case {
+    rom: &'static str,
+    platform: Platform,
+    // written at 0x1FF to skip the menu of the Timendus test suite
+    preset: Option<u8>,
+    // (frame, key, pressed)
+    keys: &'static [(u32, usize, bool)],
+    frames: u32,
+}
+
+fn golden_path(name: &str, platform: Platform) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}-{}.txt", name, platform))
+}
+
+// Compares the screen against tests/golden/<name>-<platform>.txt, writes it instead with UPDATE_GOLDEN=1
+fn check_golden(name: &str, platform: Platform, interpreter: &Interpreter) {
+    let actual = interpreter.cpu.display.to_string().replace(' ', ".");
+    let actual = actual.trim_end().to_string() + "\n";
+    let path = golden_path(name, platform);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing {}, run with UPDATE_GOLDEN=1 and check the image:\n{}",
+            path.display(),
+            actual
+        )
+    });
+    assert!(
+        expected == actual,
+        "{} on {} differs from {}, got:\n{}",
+        name,
+        platform,
+        path.display(),
+        actual
+    );
+}
+
+fn run(
+    program: &Program,
+    platform: Platform,
+    preset: Option<u8>,
+    keys: &[(u32, usize, bool)],
+    frames: u32,
+) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.quirks = platform.quirks();
+    interpreter.tickrate = TICKRATE;
+    interpreter.load_program(program).unwrap();
+    if let Some(preset) = preset {
+        interpreter.cpu.memory[0x1FF] = preset;
+    }
+
+    let mut headless = Headless::new(frames as u64);
+    headless.keys = keys.iter().map(|&(f, k, p)| (f as u64, k, p)).collect();
+    let mut machine = Machine::new(interpreter, headless);
+    machine.run();
+    machine.interpreter
+}
+
+fn run_case(case: &Case) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/roms")
+        .join(format!("{}.ch8", case.rom));
+    let program = Program::from_path(&path)
+        .unwrap_or_else(|e| panic!("{} : {}, see tests/roms/README.md", path.display(), e));
+    let interpreter = run(&program, case.platform, case.preset, case.keys, case.frames);
+    check_golden(case.rom, case.platform, &interpreter);
+}
+
+const PLATFORMS: [Platform; 3] = [Platform::Chip8, Platform::SuperChip, Platform::XoChip];
+
+#[test]
+#[ignore = "needs the Timendus test suite in tests/roms"]
+fn chip8_logo() {
+    run_case(&Case {
+        rom: "1-chip8-logo",
+        platform: Platform::Chip8,
+        preset: None,
+        keys: &[],
+        frames: 60,
+    });
+}
+
+#[test]
+#[ignore = "needs the Timendus test suite in tests/roms"]
+fn ibm_logo() {
+    run_case(&Case {
+        rom: "2-ibm-logo",
+        platform: Platform::Chip8,
+        preset: None,
+        keys: &[],
+        frames: 60,
+    });
+}
+
+#[test]
+#[ignore = "needs the Timendus test suite in tests/roms"]
+fn corax() {
+    for platform in PLATFORMS {
+        run_case(&Case {
+            rom: "3-corax+",
+            platform,
+            preset: None,
+            keys: &[],
+            frames: 120,
+        });
+    }
+}
+
+#[test]
+#[ignore = "needs the Timendus test suite in tests/roms"]
+fn flags() {
+    for platform in PLATFORMS {
+        run_case(&Case {
+            rom: "4-flags",
+            platform,
+            preset: None,
+            keys: &[],
+            frames: 120,
+        });
+    }
+}
+
+#[test]
+#[ignore = "needs the Timendus test suite in tests/roms"]
+fn quirks() {
+    // the menu entries of the quirks test, in the order of PLATFORMS
+    for (platform, preset) in PLATFORMS.into_iter().zip(1..) {
+        run_case(&Case {
+            rom: "5-quirks",
+            platform,
+            preset: Some(preset),
+            keys: &[],
+            frames: 600,
+        });
+    }
+}
+
+#[test]
+#[ignore = "needs the Timendus test suite in tests/roms"]
+fn keypad() {
+    // FX0A test : press and release 5
+    run_case(&Case {
+        rom: "6-keypad",
+        platform: Platform::Chip8,
+        preset: Some(3),
+        keys: &[(30, 0x5, true), (35, 0x5, false)],
+        frames: 60,
+    });
+}
+
+// (x, y, low nibble of the 8XYN opcode, Vx, Vy, expected Vx, expected VF)
+type FlagCheck = (u8, u8, u8, u8, u8, u8, u8);
+
+// VF holds 0xAA before every check, to see whether it is written at all
+fn flag_checks(quirks: &Quirks) -> Vec<FlagCheck> {
+    let reset = if quirks.vf_reset { 0x00 } else { 0xAA };
+    let (shr, shl) = if quirks.shifting {
+        ((0x01, 1), (0x02, 1))
+    } else {
+        ((0x40, 0), (0x02, 0))
+    };
+    vec![
+        (1, 2, 0x1, 0x0F, 0xF0, 0xFF, reset),
+        (1, 2, 0x2, 0x0F, 0xFF, 0x0F, reset),
+        (1, 2, 0x3, 0x0F, 0xFF, 0xF0, reset),
+        (1, 2, 0x4, 0x10, 0x20, 0x30, 0),
+        (1, 2, 0x4, 0xF0, 0x20, 0x10, 1),
+        (1, 2, 0x5, 0x30, 0x10, 0x20, 1),
+        (1, 2, 0x5, 0x10, 0x30, 0xE0, 0),
+        (1, 2, 0x5, 0x10, 0x10, 0x00, 1),
+        (1, 2, 0x7, 0x10, 0x30, 0x20, 1),
+        (1, 2, 0x7, 0x30, 0x10, 0xE0, 0),
+        (1, 2, 0x6, 0x03, 0x80, shr.0, shr.1),
+        (1, 2, 0xE, 0x81, 0x01, shl.0, shl.1),
+        // the flag wins over the result when VF is the destination
+        (0xF, 2, 0x4, 0xF0, 0x20, 1, 1),
+        (0xF, 2, 0x5, 0x10, 0x30, 0, 0),
+        (0xF, 2, 0x7, 0x30, 0x10, 0, 0),
+    ]
+}
+
+// Draws a C for every check that passes and an E for every one that fails, 12 per row
+fn flags_rom(quirks: &Quirks) -> Vec<u8> {
+    let mut code = Vec::new();
+    for (i, (x, y, n, vx, vy, expected_vx, expected_vf)) in
+        flag_checks(quirks).into_iter().enumerate()
+    {
+        code.extend([
+            Instruction::LdVxByte { x: 0xF, byte: 0xAA },
+            Instruction::LdVxByte { x, byte: vx },
+            Instruction::LdVxByte { x: y, byte: vy },
+        ]);
+        code.push(
+            Instruction::decode(0x8000 | (x as u16) << 8 | (y as u16) << 4 | n as u16).unwrap(),
+        );
+        code.extend([
+            Instruction::LdVxVy { x: 3, y: 0xF },
+            Instruction::LdVxByte { x: 4, byte: 0xC },
+            Instruction::SeVxByte {
+                x,
+                byte: expected_vx,
+            },
+            Instruction::LdVxByte { x: 4, byte: 0xE },
+            Instruction::SeVxByte {
+                x: 3,
+                byte: expected_vf,
+            },
+            Instruction::LdVxByte { x: 4, byte: 0xE },
+            Instruction::LdFVx { x: 4 },
+            Instruction::LdVxByte {
+                x: 5,
+                byte: (i % 12) as u8 * 5,
+            },
+            Instruction::LdVxByte {
+                x: 6,
+                byte: (i / 12) as u8 * 6,
+            },
+            Instruction::DrwVxVyNibble {
+                x: 5,
+                y: 6,
+                nibble: 5,
+            },
+        ]);
+    }
+    let end = 0x200 + 2 * code.len() as u16;
+    code.push(Instruction::JpAddr { addr: end });
+
+    code.iter().flat_map(|i| i.encode().to_be_bytes()).collect()
+}
+
+#[test]
+fn builtin_flags() {
+    for platform in PLATFORMS {
+        let program = Program::from(flags_rom(&platform.quirks()));
+        let interpreter = run(&program, platform, None, &[], 60);
+        check_golden("builtin-flags", platform, &interpreter);
+    }
+}
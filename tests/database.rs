@@ -0,0 +1,107 @@
+use chip8::chip8::database::Database;
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::platform::Platform;
+use chip8::chip8::program::Program;
+
+const ROM: [u8; 4] = [0x60, 0x01, 0x12, 0x02];
+
+// Two programs in the format of the chip-8-database programs.json
+fn database() -> Database {
+    let sha1 = Program::from(ROM.to_vec()).sha1_hex();
+    let json = r##"[
+      {
+        "title": "Breakout",
+        "authors": ["Someone"],
+        "release": "1979",
+        "roms": {
+          "SHA1": {
+            "file": "breakout.ch8",
+            "platforms": ["superchip", "originalChip8"],
+            "quirkyPlatforms": { "superchip": { "shift": false, "wrap": true } },
+            "tickrate": 30,
+            "startAddress": 1536,
+            "keys": { "left": 4, "right": 6, "a": 5, "player2Up": 1 },
+            "colors": { "pixels": ["#102030", "#ffffff"], "buzzer": "#ff0000" }
+          }
+        }
+      },
+      {
+        "title": "Tank",
+        "roms": {
+          "0000000000000000000000000000000000000000": { "platforms": ["megachip8x"] }
+        }
+      }
+    ]"##;
+    Database::parse(&json.replace("SHA1", &sha1.to_uppercase())).unwrap()
+}
+
+#[test]
+fn finds_roms_by_hash() {
+    let database = database();
+    assert_eq!(database.len(), 2);
+    let entry = database.lookup(&Program::from(ROM.to_vec())).unwrap();
+    assert_eq!(entry.title, "Breakout");
+    assert_eq!(entry.authors, ["Someone"]);
+    assert_eq!(entry.tickrate, Some(30));
+    assert_eq!(entry.start_address, Some(0x600));
+    assert_eq!(entry.keys["left"], 4);
+
+    let colors = entry.colors.unwrap();
+    assert_eq!(colors.pixels, [[0x10, 0x20, 0x30], [0xFF, 0xFF, 0xFF]]);
+    assert_eq!(colors.buzzer, Some([0xFF, 0, 0]));
+    assert!(database.lookup(&Program::from(vec![0])).is_none());
+}
+
+#[test]
+fn takes_the_quirks_of_the_first_platform() {
+    let entry = database().lookup(&Program::from(ROM.to_vec())).unwrap();
+    assert_eq!(entry.platform, Some(Platform::SuperChip));
+    let quirks = entry.quirks.unwrap();
+    assert!(!quirks.shifting);
+    assert!(!quirks.clipping);
+    assert!(quirks.jumping);
+
+    // a platform we do not know gives no quirks
+    let entry = database().get(&"0".repeat(40)).unwrap();
+    assert_eq!(entry.title, "Tank");
+    assert_eq!(entry.platform, None);
+    assert_eq!(entry.quirks, None);
+}
+
+#[test]
+fn applies_quirks_and_tickrate() {
+    let entry = database().lookup(&Program::from(ROM.to_vec())).unwrap();
+    let mut interpreter = Interpreter::new();
+    entry.apply(&mut interpreter);
+    assert_eq!(interpreter.tickrate, 30);
+    assert_eq!(Some(interpreter.quirks), entry.quirks);
+}
+
+#[test]
+fn searches_titles() {
+    let database = database();
+    let found = database.search("BREAK");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].1, "Breakout");
+    assert!(database.search("pong").is_empty());
+}
+
+#[test]
+fn parses_the_embedded_database() {
+    Database::embedded();
+    assert!(Database::parse("{").is_err());
+}
+
+// Checks the entries of data/programs.json, whichever release it comes from
+#[test]
+fn finds_every_embedded_rom() {
+    let database = Database::embedded();
+    for (sha1, title) in database.search("") {
+        assert!(
+            sha1.len() == 40 && sha1.bytes().all(|b| b.is_ascii_hexdigit()),
+            "{}",
+            sha1
+        );
+        assert_eq!(database.get(sha1).unwrap().title, title);
+    }
+}
@@ -0,0 +1,101 @@
+use chip8::chip8::debugger::Debugger;
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::program::Program;
+
+// V0 += 1 in a loop calling a subroutine at 208
+const ROM: [u8; 12] = [
+    0x70, 0x01, 0x22, 0x08, 0x12, 0x00, 0x00, 0x00, 0xA2, 0x34, 0x00, 0xEE,
+];
+
+fn debugger() -> Debugger {
+    let mut interpreter = Interpreter::new();
+    interpreter.tickrate = 10;
+    interpreter.load_program(&Program::from(ROM.to_vec())).unwrap();
+    Debugger::new(interpreter)
+}
+
+#[test]
+fn starts_paused_and_steps() {
+    let mut debugger = debugger();
+    debugger.frame();
+    assert_eq!(debugger.interpreter.cpu.program_counter, 0x200);
+
+    debugger.step();
+    debugger.step();
+    assert_eq!(debugger.interpreter.cpu.program_counter, 0x208);
+    assert_eq!(debugger.cursor, 0x208);
+    assert_eq!(debugger.call_stack(), [" 1 204"]);
+}
+
+#[test]
+fn stops_at_breakpoints() {
+    let mut debugger = debugger();
+    debugger.toggle_breakpoint(0x20A);
+    debugger.resume();
+    debugger.frame();
+    assert!(!debugger.running);
+    assert_eq!(debugger.status, "breakpoint at 20A");
+    assert_eq!(debugger.interpreter.cpu.program_counter, 0x20A);
+
+    // resuming runs the instruction under the breakpoint
+    debugger.resume();
+    debugger.frame();
+    assert_eq!(debugger.interpreter.cpu.program_counter, 0x20A);
+    assert_eq!(debugger.interpreter.cpu.registers[0], 2);
+
+    debugger.toggle_breakpoint(0x20A);
+    debugger.resume();
+    debugger.frame();
+    assert!(debugger.running);
+}
+
+#[test]
+fn runs_to_the_cursor() {
+    let mut debugger = debugger();
+    debugger.scroll(2);
+    assert_eq!(debugger.cursor, 0x204);
+    debugger.run_to_cursor();
+    debugger.frame();
+    assert!(!debugger.running);
+    assert_eq!(debugger.interpreter.cpu.program_counter, 0x204);
+    assert_eq!(debugger.interpreter.cpu.index, 0x234);
+}
+
+#[test]
+fn describes_the_state() {
+    let mut debugger = debugger();
+    debugger.toggle_breakpoint(0x202);
+    // centered on the cursor
+    let code = debugger.disassembly(4);
+    assert_eq!(code.len(), 4);
+    assert_eq!(code[0].0, 0x1FC);
+    assert_eq!(code[2], (0x200, String::from(" > 200  7001  ADD V0, 1")));
+    assert_eq!(code[3], (0x202, String::from("*  202  2208  CALL 208")));
+
+    debugger.step();
+    let registers = debugger.registers();
+    assert_eq!(registers[0], "V0 01  V1 00  V2 00  V3 00");
+    assert_eq!(registers[4], "I 000  SP 0  DT 00  ST 00");
+    assert_eq!(registers[5], "PC 202");
+
+    debugger.interpreter.cpu.index = 0x20B;
+    assert_eq!(
+        debugger.memory(2),
+        ["208  A2 34 00 EE 00 00 00 00", "210  00 00 00 00 00 00 00 00"]
+    );
+}
+
+#[test]
+fn stops_at_the_end_of_memory() {
+    // JP FFF then a second frame, the display wait looks at FFF too
+    let mut interpreter = Interpreter::new();
+    interpreter.tickrate = 10;
+    interpreter
+        .load_program(&Program::from(vec![0x1F, 0xFF]))
+        .unwrap();
+    let mut debugger = Debugger::new(interpreter);
+    debugger.resume();
+    debugger.frame();
+    assert!(!debugger.running);
+    assert_eq!(debugger.status, "program counter FFF out of memory");
+}
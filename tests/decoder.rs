@@ -0,0 +1,268 @@
+use chip8::chip8::assembler::assemble_line;
+use chip8::chip8::decoder::decode;
+use chip8::chip8::disassembler::Disassembler;
+use chip8::chip8::instructions::{Chip8, Instruction};
+use chip8::chip8::program::Program;
+
+// Records every trait method called by the decoder
+#[derive(Default)]
+struct Calls(Vec<&'static str>);
+
+impl Chip8 for Calls {
+    fn cls(&mut self) {
+        self.0.push("cls")
+    }
+    fn ret(&mut self) {
+        self.0.push("ret")
+    }
+    fn sys_addr(&mut self, _addr: u16) {
+        self.0.push("sys_addr")
+    }
+    fn jp_addr(&mut self, _addr: u16) {
+        self.0.push("jp_addr")
+    }
+    fn call_addr(&mut self, _addr: u16) {
+        self.0.push("call_addr")
+    }
+    fn se_vx_byte(&mut self, _x: u8, _byte: u8) {
+        self.0.push("se_vx_byte")
+    }
+    fn sne_vx_byte(&mut self, _x: u8, _byte: u8) {
+        self.0.push("sne_vx_byte")
+    }
+    fn se_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("se_vx_vy")
+    }
+    fn ld_vx_byte(&mut self, _x: u8, _byte: u8) {
+        self.0.push("ld_vx_byte")
+    }
+    fn add_vx_byte(&mut self, _x: u8, _byte: u8) {
+        self.0.push("add_vx_byte")
+    }
+    fn ld_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("ld_vx_vy")
+    }
+    fn or_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("or_vx_vy")
+    }
+    fn and_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("and_vx_vy")
+    }
+    fn xor_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("xor_vx_vy")
+    }
+    fn add_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("add_vx_vy")
+    }
+    fn sub_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("sub_vx_vy")
+    }
+    fn shr_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("shr_vx_vy")
+    }
+    fn subn_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("subn_vx_vy")
+    }
+    fn shl_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("shl_vx_vy")
+    }
+    fn sne_vx_vy(&mut self, _x: u8, _y: u8) {
+        self.0.push("sne_vx_vy")
+    }
+    fn ld_i_addr(&mut self, _addr: u16) {
+        self.0.push("ld_i_addr")
+    }
+    fn jp_v0_addr(&mut self, _addr: u16) {
+        self.0.push("jp_v0_addr")
+    }
+    fn rnd_vx_byte(&mut self, _x: u8, _byte: u8) {
+        self.0.push("rnd_vx_byte")
+    }
+    fn drw_vx_vy_nibble(&mut self, _x: u8, _y: u8, _nibble: u16) {
+        self.0.push("drw_vx_vy_nibble")
+    }
+    fn skp_vx(&mut self, _x: u8) {
+        self.0.push("skp_vx")
+    }
+    fn sknp_vx(&mut self, _x: u8) {
+        self.0.push("sknp_vx")
+    }
+    fn ld_vx_dt(&mut self, _x: u8) {
+        self.0.push("ld_vx_dt")
+    }
+    fn ld_vx_k(&mut self, _x: u8) {
+        self.0.push("ld_vx_k")
+    }
+    fn ld_dt_vx(&mut self, _x: u8) {
+        self.0.push("ld_dt_vx")
+    }
+    fn ld_st_vx(&mut self, _x: u8) {
+        self.0.push("ld_st_vx")
+    }
+    fn add_i_vx(&mut self, _x: u8) {
+        self.0.push("add_i_vx")
+    }
+    fn ld_f_vx(&mut self, _x: u8) {
+        self.0.push("ld_f_vx")
+    }
+    fn ld_b_vx(&mut self, _x: u8) {
+        self.0.push("ld_b_vx")
+    }
+    fn ld_i_vx(&mut self, _x: u8) {
+        self.0.push("ld_i_vx")
+    }
+    fn ld_vx_i(&mut self, _x: u8) {
+        self.0.push("ld_vx_i")
+    }
+    fn unknown(&mut self, _op: u16) {
+        self.0.push("unknown")
+    }
+}
+
+// The opcode the instruction encodes to, 5XYN and 9XYN ignore N like on the VIP
+fn canonical(op: u16) -> u16 {
+    match op & 0xF000 {
+        0x5000 | 0x9000 => op & 0xFFF0,
+        _ => op,
+    }
+}
+
+fn calls(op: u16) -> Vec<&'static str> {
+    let mut calls = Calls::default();
+    decode(op, &mut calls);
+    calls.0
+}
+
+#[test]
+fn every_opcode_calls_exactly_one_method() {
+    for op in 0..=u16::MAX {
+        let calls = calls(op);
+        assert_eq!(calls.len(), 1, "{:04X} calls {:?}", op, calls);
+
+        match Instruction::decode(op) {
+            Ok(instruction) => {
+                assert_ne!(calls[0], "unknown", "{:04X}", op);
+                assert_eq!(
+                    instruction.encode(),
+                    canonical(op),
+                    "{:04X} encodes back",
+                    op
+                );
+                let mut visited = Calls::default();
+                instruction.visit(&mut visited);
+                assert_eq!(visited.0, calls, "{:04X} visits the same method", op);
+            }
+            Err(_) => assert_eq!(calls[0], "unknown", "{:04X}", op),
+        }
+    }
+}
+
+#[test]
+fn every_opcode_disassembles_and_assembles_back() {
+    for op in 0..=u16::MAX {
+        let mut disassembler = Disassembler::new();
+        disassembler.step(op);
+        let text = disassembler.to_string();
+        assert_eq!(
+            assemble_line(&text),
+            Ok(canonical(op)),
+            "{:04X} disassembles to {}",
+            op,
+            text.trim_end()
+        );
+    }
+}
+
+#[test]
+fn instruction_display_matches_the_disassembler() {
+    for op in 0..=u16::MAX {
+        if let Ok(instruction) = Instruction::decode(op) {
+            let mut disassembler = Disassembler::new();
+            disassembler.step(op);
+            assert_eq!(disassembler.to_string().trim_end(), instruction.to_string());
+            assert_eq!(instruction.to_string().parse(), Ok(instruction));
+        }
+    }
+}
+
+#[test]
+fn machine_code_calls_reach_sys_addr() {
+    assert_eq!(calls(0x0123), ["sys_addr"]);
+    assert_eq!(calls(0x00E0), ["cls"]);
+    assert_eq!(calls(0x00EE), ["ret"]);
+    // SCHIP scroll and exit
+    assert_eq!(calls(0x00C4), ["unknown"]);
+    assert_eq!(calls(0x00FD), ["unknown"]);
+}
+
+#[test]
+fn skips_on_registers_ignore_the_last_nibble() {
+    assert_eq!(calls(0x5121), ["se_vx_vy"]);
+    assert_eq!(calls(0x912F), ["sne_vx_vy"]);
+    assert_eq!(
+        Instruction::decode(0x5123),
+        Ok(Instruction::SeVxVy { x: 1, y: 2 })
+    );
+}
+
+#[test]
+fn assembler_accepts_prefixes_and_comments() {
+    assert_eq!(assemble_line("ld va, 0x1f ; comment"), Ok(0x6A1F));
+    assert_eq!(assemble_line("JP #2A0"), Ok(0x12A0));
+    assert_eq!(assemble_line("drw v1, v2, $5"), Ok(0xD125));
+    assert!(assemble_line("LD V1, 100").is_err());
+    assert!(assemble_line("DRW V1, V2, 10").is_err());
+    assert!(assemble_line("MOV V1, V2").is_err());
+    assert!(assemble_line("OR V1, 2").is_err());
+}
+
+// A fixed buffer, as on a target without an allocator
+struct Buffer {
+    bytes: [u8; 16],
+    len: usize,
+}
+
+impl std::fmt::Write for Buffer {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let end = self.len + s.len();
+        if end > self.bytes.len() {
+            return Err(std::fmt::Error);
+        }
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[test]
+fn disassembles_a_program_word_by_word() {
+    let mut disassembler = Disassembler::new();
+    disassembler.disassemble(&Program::from(vec![0x00, 0xE0, 0x6A, 0x1F, 0x12, 0x00]));
+    assert_eq!(disassembler.to_string(), "CLS\nLD VA, 1F\nJP 200\n");
+
+    // the last byte of an odd size
+    let mut disassembler = Disassembler::new();
+    disassembler.disassemble(&Program::from(vec![0x00, 0xE0, 0xA3]));
+    assert_eq!(disassembler.to_string(), "CLS\nLD I, 300\n");
+}
+
+#[test]
+fn disassembler_writes_into_any_writer() {
+    let mut disassembler = Disassembler::with_writer(Buffer {
+        bytes: [0; 16],
+        len: 0,
+    });
+    disassembler.step(0x00E0);
+    disassembler.step(0x6A1F);
+    let buffer = disassembler.finish().unwrap();
+    assert_eq!(&buffer.bytes[..buffer.len], b"CLS\nLD VA, 1F\n");
+
+    let mut disassembler = Disassembler::with_writer(Buffer {
+        bytes: [0; 16],
+        len: 0,
+    });
+    for _ in 0..3 {
+        disassembler.step(0x6A1F);
+    }
+    assert!(disassembler.finish().is_err());
+}
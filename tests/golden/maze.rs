@@ -0,0 +1,183 @@
+// Recompiled from maze.ch8 by `chip8 recompile`
+use chip8::chip8::instructions::Chip8;
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::program::Program;
+
+const ROM: [u8; 70] = [
+    0xA2, 0x3C, 0xC0, 0x01, 0x30, 0x01, 0xA2, 0x40, 0xD1, 0x24, 0x71, 0x04, 0x31, 0x40, 0x12, 0x00,
+    0x61, 0x00, 0x72, 0x04, 0x32, 0x20, 0x12, 0x00, 0x22, 0x30, 0x64, 0x05, 0xF4, 0x15, 0xF4, 0x07,
+    0x34, 0x00, 0x12, 0x1E, 0x00, 0xE0, 0x61, 0x00, 0x62, 0x00, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x73, 0x01, 0xA2, 0x44, 0xF3, 0x33, 0xF2, 0x65, 0x00, 0xEE, 0x00, 0x00, 0x80, 0x40, 0x20, 0x10,
+    0x10, 0x20, 0x40, 0x80, 0x00, 0x00,
+];
+
+pub fn load(i: &mut Interpreter) {
+    i.load_program(&Program::from(ROM.to_vec())).unwrap();
+}
+
+// Runs a block from the program counter when it fits in the budget, returns the number of
+// instructions executed
+pub fn step(i: &mut Interpreter, budget: u32) -> u32 {
+    let pc = i.cpu.program_counter;
+    let (start, end, len, block): (u16, u16, u32, fn(&mut Interpreter) -> u16) = match pc {
+        0x200 => (0x200, 0x206, 3, block_200),
+        0x206 => (0x206, 0x208, 1, block_206),
+        0x208 => (0x208, 0x20E, 3, block_208),
+        0x20E => (0x20E, 0x210, 1, block_20e),
+        0x210 => (0x210, 0x216, 3, block_210),
+        0x216 => (0x216, 0x218, 1, block_216),
+        0x218 => (0x218, 0x21A, 1, block_218),
+        0x21A => (0x21A, 0x21E, 2, block_21a),
+        0x21E => (0x21E, 0x222, 2, block_21e),
+        0x222 => (0x222, 0x224, 1, block_222),
+        0x224 => (0x224, 0x22C, 4, block_224),
+        0x230 => (0x230, 0x236, 3, block_230),
+        0x236 => (0x236, 0x23A, 2, block_236),
+        _ => {
+            i.step();
+            return 1;
+        }
+    };
+    // the frame ends inside the block, or the code was modified since it was recompiled
+    if len > budget || i.cpu.memory[start as usize..end as usize] != ROM[(start - 0x200) as usize..(end - 0x200) as usize] {
+        i.step();
+        return 1;
+    }
+    i.cpu.program_counter = block(i);
+    len
+}
+
+// Runs the instructions of a frame then ticks the timers, like Interpreter::run_frame
+pub fn run_frame(i: &mut Interpreter) {
+    // the VIP clock counts the cycles of every instruction
+    if i.clock.is_some() {
+        i.run_frame();
+        return;
+    }
+    let mut executed = 0;
+    while executed < i.tickrate {
+        if i.waits_display(executed == 0) {
+            break;
+        }
+        executed += step(i, i.tickrate - executed);
+    }
+    i.tick_timers();
+}
+
+#[allow(unused_variables)]
+fn block_200(i: &mut Interpreter) -> u16 {
+    // 200: LD I, 23C
+    i.cpu.index = 0x23C;
+    // 202: RND V0, 1
+    i.cpu.program_counter = 0x204;
+    i.rnd_vx_byte(0, 0x01);
+    // 204: SE V0, 1
+    if i.cpu.registers[0] == 0x01 { 0x208 } else { 0x206 }
+}
+
+#[allow(unused_variables)]
+fn block_206(i: &mut Interpreter) -> u16 {
+    // 206: LD I, 240
+    i.cpu.index = 0x240;
+    0x208
+}
+
+#[allow(unused_variables)]
+fn block_208(i: &mut Interpreter) -> u16 {
+    // 208: DRW V1, V2, 4
+    i.cpu.program_counter = 0x20A;
+    i.drw_vx_vy_nibble(1, 2, 4);
+    // 20A: ADD V1, 4
+    i.cpu.registers[1] = i.cpu.registers[1].wrapping_add(0x04);
+    // 20C: SE V1, 40
+    if i.cpu.registers[1] == 0x40 { 0x210 } else { 0x20E }
+}
+
+#[allow(unused_variables)]
+fn block_20e(i: &mut Interpreter) -> u16 {
+    // 20E: JP 200
+    0x200
+}
+
+#[allow(unused_variables)]
+fn block_210(i: &mut Interpreter) -> u16 {
+    // 210: LD V1, 0
+    i.cpu.registers[1] = 0x00;
+    // 212: ADD V2, 4
+    i.cpu.registers[2] = i.cpu.registers[2].wrapping_add(0x04);
+    // 214: SE V2, 20
+    if i.cpu.registers[2] == 0x20 { 0x218 } else { 0x216 }
+}
+
+#[allow(unused_variables)]
+fn block_216(i: &mut Interpreter) -> u16 {
+    // 216: JP 200
+    0x200
+}
+
+#[allow(unused_variables)]
+fn block_218(i: &mut Interpreter) -> u16 {
+    // 218: CALL 230
+    i.cpu.program_counter = 0x21A;
+    i.call_addr(0x230);
+    i.cpu.program_counter
+}
+
+#[allow(unused_variables)]
+fn block_21a(i: &mut Interpreter) -> u16 {
+    // 21A: LD V4, 5
+    i.cpu.registers[4] = 0x05;
+    // 21C: LD DT, V4
+    i.cpu.delay_timer = i.cpu.registers[4];
+    0x21E
+}
+
+#[allow(unused_variables)]
+fn block_21e(i: &mut Interpreter) -> u16 {
+    // 21E: LD V4, DT
+    i.cpu.registers[4] = i.cpu.delay_timer;
+    // 220: SE V4, 0
+    if i.cpu.registers[4] == 0x00 { 0x224 } else { 0x222 }
+}
+
+#[allow(unused_variables)]
+fn block_222(i: &mut Interpreter) -> u16 {
+    // 222: JP 21E
+    0x21E
+}
+
+#[allow(unused_variables)]
+fn block_224(i: &mut Interpreter) -> u16 {
+    // 224: CLS
+    i.cpu.program_counter = 0x226;
+    i.cls();
+    // 226: LD V1, 0
+    i.cpu.registers[1] = 0x00;
+    // 228: LD V2, 0
+    i.cpu.registers[2] = 0x00;
+    // 22A: JP 200
+    0x200
+}
+
+#[allow(unused_variables)]
+fn block_230(i: &mut Interpreter) -> u16 {
+    // 230: ADD V3, 1
+    i.cpu.registers[3] = i.cpu.registers[3].wrapping_add(0x01);
+    // 232: LD I, 244
+    i.cpu.index = 0x244;
+    // 234: LD B, V3
+    i.cpu.program_counter = 0x236;
+    i.ld_b_vx(3);
+    0x236
+}
+
+#[allow(unused_variables)]
+fn block_236(i: &mut Interpreter) -> u16 {
+    // 236: LD V2, [I]
+    i.cpu.program_counter = 0x238;
+    i.ld_vx_i(2);
+    // 238: RET
+    i.cpu.program_counter = 0x23A;
+    i.ret();
+    i.cpu.program_counter
+}
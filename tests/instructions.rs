@@ -0,0 +1,91 @@
+use chip8::chip8::disassembler::Disassembler;
+use chip8::chip8::instructions::{Instruction, UnknownOpcode};
+
+// The opcode the instruction encodes to, 5XYN and 9XYN ignore N like on the VIP
+fn canonical(op: u16) -> u16 {
+    match op & 0xF000 {
+        0x5000 | 0x9000 => op & 0xFFF0,
+        _ => op,
+    }
+}
+
+#[test]
+fn every_instruction_encodes_back() {
+    for op in 0..=u16::MAX {
+        match Instruction::decode(op) {
+            Ok(instruction) => {
+                assert_eq!(instruction.encode(), canonical(op), "{:04X}", op);
+                assert_eq!(u16::from(instruction), canonical(op));
+                assert_eq!(Instruction::try_from(op), Ok(instruction));
+            }
+            Err(error) => assert_eq!(error, UnknownOpcode(op)),
+        }
+    }
+}
+
+#[test]
+fn visits_the_decoded_method() {
+    for op in 0..=u16::MAX {
+        if let Ok(instruction) = Instruction::decode(op) {
+            let mut visited = Disassembler::new();
+            instruction.visit(&mut visited);
+            let mut decoded = Disassembler::new();
+            decoded.step(op);
+            assert_eq!(visited.to_string(), decoded.to_string(), "{:04X}", op);
+        }
+    }
+}
+
+#[test]
+fn displays_the_mnemonic() {
+    let cases = [
+        (0x00E0, "CLS"),
+        (0x22A0, "CALL 2A0"),
+        (0x5120, "SE V1, V2"),
+        (0x8AB6, "SHR VA, VB"),
+        (0xB300, "JP V0, 300"),
+        (0xD125, "DRW V1, V2, 5"),
+        (0xF30A, "LD V3, K"),
+        (0xFA55, "LD [I], VA"),
+        (0xFA65, "LD VA, [I]"),
+    ];
+    for (op, text) in cases {
+        assert_eq!(Instruction::decode(op).unwrap().to_string(), text);
+    }
+}
+
+#[test]
+fn unknown_opcodes_are_errors() {
+    assert_eq!(Instruction::decode(0xE1A0), Err(UnknownOpcode(0xE1A0)));
+    assert_eq!(Instruction::decode(0xF0FF), Err(UnknownOpcode(0xF0FF)));
+    assert_eq!(UnknownOpcode(0xE1A0).to_string(), "unknown instruction : E1A0");
+}
+
+#[test]
+fn encoding_truncates_the_operands() {
+    let cases = [
+        (Instruction::JpAddr { addr: 0x1234 }, 0x1234),
+        (Instruction::SysAddr { addr: 0xF0E0 }, 0x00E0),
+        (Instruction::LdIAddr { addr: 0xFFFF }, 0xAFFF),
+        (
+            Instruction::LdVxByte {
+                x: 0x12,
+                byte: 0x34,
+            },
+            0x6234,
+        ),
+        (Instruction::SubVxVy { x: 0xF1, y: 0x1A }, 0x81A5),
+        (
+            Instruction::DrwVxVyNibble {
+                x: 0x10,
+                y: 0x21,
+                nibble: 0x15,
+            },
+            0xD015,
+        ),
+        (Instruction::LdVxK { x: 0x13 }, 0xF30A),
+    ];
+    for (instruction, op) in cases {
+        assert_eq!(instruction.encode(), op, "{:?}", instruction);
+    }
+}
@@ -0,0 +1,165 @@
+#![cfg(all(feature = "jit", target_arch = "x86_64", unix))]
+
+use chip8::chip8::cpu::Cpu;
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::jit::Jit;
+use chip8::chip8::program::Program;
+use chip8::chip8::sys::{Routines, SysPolicy};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const LEN: u16 = 96;
+
+// Random instructions the interpreter runs without panicking : data writes go to 0x400
+fn random_rom(rng: &mut StdRng) -> Vec<u8> {
+    let mut rom = Vec::new();
+    for _ in 0..LEN {
+        let x = rng.gen_range(0..16u16);
+        let y = rng.gen_range(0..16u16);
+        let byte = rng.gen::<u8>() as u16;
+        let target = 0x200 + 2 * rng.gen_range(0..LEN);
+        let op = match rng.gen_range(0..20) {
+            0 => 0x6000 | x << 8 | byte,
+            1 => 0x7000 | x << 8 | byte,
+            2 => 0x8000 | x << 8 | y << 4,
+            3 => 0x8001 | x << 8 | y << 4,
+            4 => 0x8002 | x << 8 | y << 4,
+            5 => 0x8003 | x << 8 | y << 4,
+            6 => 0x8004 | x << 8 | y << 4,
+            7 => 0x3000 | x << 8 | byte,
+            8 => 0x4000 | x << 8 | byte,
+            9 => 0x5000 | x << 8 | y << 4,
+            10 => 0x9000 | x << 8 | y << 4,
+            11 => 0x1000 | target,
+            12 => 0xA400 | byte,
+            13 => 0xF007 | x << 8,
+            14 => 0xF015 | x << 8,
+            15 => 0xF018 | x << 8,
+            16 => 0xD000 | x << 8 | y << 4 | rng.gen_range(0..16u16),
+            17 => 0x00E0,
+            // I is reloaded before every write so it stays in the data area
+            18 => {
+                rom.extend_from_slice(&(0xA400 | byte).to_be_bytes());
+                0xF033 | x << 8
+            }
+            _ => {
+                rom.extend_from_slice(&(0xA400 | byte).to_be_bytes());
+                0xF055 | x << 8
+            }
+        };
+        rom.extend_from_slice(&op.to_be_bytes());
+    }
+    // JP 200, twice in case the last instruction skips
+    rom.extend_from_slice(&[0x12, 0x00, 0x12, 0x00]);
+    rom
+}
+
+fn assert_same(interpreter: &Interpreter, jit: &Jit) {
+    let (a, b) = (&interpreter.cpu, &jit.interpreter.cpu);
+    assert_eq!(a.registers, b.registers);
+    assert_eq!(a.index, b.index);
+    assert_eq!(a.program_counter, b.program_counter);
+    assert_eq!(a.delay_timer, b.delay_timer);
+    assert_eq!(a.sound_timer, b.sound_timer);
+    assert_eq!(a.memory, b.memory);
+    assert_eq!(a.display.to_string(), b.display.to_string());
+}
+
+fn run_both(rom: Vec<u8>, steps: u64) {
+    run_both_with(rom, steps, |_| {});
+}
+
+// `setup` configures both interpreters before loading the program
+fn run_both_with(rom: Vec<u8>, steps: u64, setup: impl Fn(&mut Interpreter)) {
+    run_program(Program::from(rom), steps, setup);
+}
+
+fn run_program(program: Program, steps: u64, setup: impl Fn(&mut Interpreter)) {
+    // a skip over the reload of I would let the memory quirk move I into the code
+    let mut interpreter = Interpreter::new();
+    interpreter.quirks.memory = false;
+    setup(&mut interpreter);
+    interpreter.load_program(&program).unwrap();
+    let mut jit = Jit::new();
+    jit.interpreter.quirks.memory = false;
+    setup(&mut jit.interpreter);
+    jit.load_program(&program).unwrap();
+
+    // compare along the way, not only at the end
+    for _ in 0..steps / 100 {
+        for _ in 0..100 {
+            interpreter.step();
+        }
+        jit.run(100);
+        assert_same(&interpreter, &jit);
+    }
+}
+
+#[test]
+fn random_programs_match_the_interpreter() {
+    let mut rng = StdRng::seed_from_u64(0xC8);
+    for _ in 0..200 {
+        let rom = random_rom(&mut rng);
+        run_both(rom, 5_000);
+    }
+}
+
+#[test]
+fn random_programs_without_vf_reset_match_the_interpreter() {
+    let mut rng = StdRng::seed_from_u64(0xC9);
+    for _ in 0..50 {
+        let rom = random_rom(&mut rng);
+        run_both_with(rom, 5_000, |interpreter| {
+            interpreter.quirks.vf_reset = false
+        });
+    }
+}
+
+#[test]
+fn self_modifying_code_matches_the_interpreter() {
+    let rom = vec![
+        0x70, 0x01, // 200: ADD V0, 1
+        0xA2, 0x09, // 202: LD I, 209
+        0xF0, 0x55, // 204: LD [I], V0  patches the operand of 208
+        0x12, 0x08, // 206: JP 208
+        0x61, 0x00, // 208: LD V1, 0
+        0x82, 0x14, // 20A: ADD V2, V1
+        0x12, 0x00, // 20C: JP 200
+    ];
+    run_both(rom, 10_000);
+}
+
+#[test]
+fn writes_wrapping_around_memory_match_the_interpreter() {
+    let rom = vec![
+        0x72, 0x01, // 000: ADD V2, 1  patched into ADD V3, 1
+        0x74, 0x01, // 002: ADD V4, 1
+        0x34, 0x14, // 004: SE V4, 20
+        0x10, 0x00, // 006: JP 000
+        0x61, 0x73, // 008: LD V1, 73
+        0xAF, 0xFF, // 00A: LD I, FFF
+        0xF1, 0x55, // 00C: LD [I], V1  writes FFF and 000
+        0x10, 0x00, // 00E: JP 000
+    ];
+    run_program(Program::from(rom).at(0).unwrap(), 10_000, |_| {});
+}
+
+// Switches the instruction at 202 between ADD V1, 1 and ADD V1, 2
+fn patch(cpu: &mut Cpu) {
+    cpu.memory[0x203] ^= 0x03;
+}
+
+#[test]
+fn sys_routines_writing_into_code_match_the_interpreter() {
+    let rom = vec![
+        0x70, 0x01, // 200: ADD V0, 1
+        0x71, 0x01, // 202: ADD V1, 1
+        0x03, 0x00, // 204: SYS 300  patches 202
+        0x12, 0x00, // 206: JP 200
+    ];
+    run_both_with(rom, 10_000, |interpreter| {
+        let mut routines = Routines::new();
+        routines.register(0x300, patch);
+        interpreter.sys = SysPolicy::Hle(routines);
+    });
+}
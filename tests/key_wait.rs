@@ -0,0 +1,76 @@
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::program::Program;
+
+// LD V2, K then an endless loop
+const WAIT: [u8; 4] = [0xF2, 0x0A, 0x12, 0x02];
+
+fn waiting() -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .load_program(&Program::from(WAIT.to_vec()))
+        .unwrap();
+    interpreter
+}
+
+#[test]
+fn waits_for_a_press_and_release() {
+    let mut interpreter = waiting();
+    interpreter.step();
+    assert!(interpreter.waiting_for_key());
+    assert_eq!(interpreter.cpu.program_counter, 0x200);
+
+    interpreter.cpu.keys[5] = true;
+    interpreter.step();
+    assert!(interpreter.waiting_for_key());
+    assert_eq!(interpreter.cpu.program_counter, 0x200);
+
+    interpreter.cpu.keys[5] = false;
+    interpreter.step();
+    assert!(!interpreter.waiting_for_key());
+    assert_eq!(interpreter.cpu.program_counter, 0x202);
+    assert_eq!(interpreter.cpu.registers[2], 5);
+}
+
+#[test]
+fn keys_held_before_the_wait_do_not_count() {
+    let mut interpreter = waiting();
+    interpreter.cpu.keys[3] = true;
+    interpreter.step();
+    interpreter.step();
+    assert!(interpreter.waiting_for_key());
+
+    // released then pressed again
+    interpreter.cpu.keys[3] = false;
+    interpreter.step();
+    interpreter.cpu.keys[3] = true;
+    interpreter.step();
+    assert!(interpreter.waiting_for_key());
+    interpreter.cpu.keys[3] = false;
+    interpreter.step();
+    assert!(!interpreter.waiting_for_key());
+    assert_eq!(interpreter.cpu.registers[2], 3);
+}
+
+#[test]
+fn loading_a_program_ends_the_wait() {
+    let mut interpreter = waiting();
+    interpreter.step();
+    assert!(interpreter.waiting_for_key());
+    interpreter
+        .load_program(&Program::from(WAIT.to_vec()))
+        .unwrap();
+    assert!(!interpreter.waiting_for_key());
+}
+
+#[test]
+fn timers_can_pause_during_the_wait() {
+    let mut interpreter = waiting();
+    interpreter.step();
+    interpreter.cpu.delay_timer = 10;
+    interpreter.tick_timers();
+    assert_eq!(interpreter.cpu.delay_timer, 9);
+
+    interpreter.pause_timers_on_key_wait = true;
+    interpreter.tick_timers();
+    assert_eq!(interpreter.cpu.delay_timer, 9);
+}
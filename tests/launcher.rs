@@ -0,0 +1,87 @@
+use chip8::chip8::archive::Rom;
+use chip8::chip8::database::Database;
+use chip8::chip8::font::{glyph, FONT};
+use chip8::chip8::launcher::{thumbnail, Launcher};
+use chip8::chip8::program::Program;
+
+// Draws the 0 of the font at (0, 0) and loops
+const DRAW: [u8; 10] = [0x00, 0xE0, 0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x08];
+
+fn rom(name: &str, content: &[u8]) -> Rom {
+    Rom {
+        name: name.to_string(),
+        program: Program::from(content.to_vec()),
+    }
+}
+
+#[test]
+fn takes_a_thumbnail() {
+    // the DRW waits for the second frame
+    let screen = thumbnail(&Program::from(DRAW.to_vec()), None, 2);
+    assert!(screen[(0, 0)] && screen[(4, 3)]);
+    assert!(!screen[(1, 1)]);
+
+    // an unknown opcode stops it without a panic
+    let screen = thumbnail(&Program::from(vec![0xFF, 0xFF]), None, 10);
+    assert!(!screen[(0, 0)]);
+    // nor a jump to the last byte of memory
+    let screen = thumbnail(&Program::from(vec![0x1F, 0xFF]), None, 10);
+    assert!(!screen[(0, 0)]);
+}
+
+#[test]
+fn thumbnails_wait_for_the_display() {
+    // XORs the 0 of the font at (0, 0) in a loop, once a frame from the
+    // second one with the display wait quirk of the default platform
+    let flicker = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x04];
+    let screen = thumbnail(&Program::from(flicker.to_vec()), None, 2);
+    assert!(screen[(0, 0)]);
+    let screen = thumbnail(&Program::from(flicker.to_vec()), None, 3);
+    assert!(!screen[(0, 0)]);
+}
+
+#[test]
+fn titles_come_from_the_database() {
+    let sha1 = Program::from(DRAW.to_vec()).sha1_hex();
+    let json = format!(r#"[{{"title": "Zero", "roms": {{"{}": {{}}}}}}]"#, sha1);
+    let database = Database::parse(&json).unwrap();
+    let roms = vec![rom("draw.ch8", &DRAW), rom("loop.ch8", &[0x12, 0x00])];
+    let launcher = Launcher::new(roms, Some(&database));
+    assert_eq!(launcher.titles, ["Zero", "loop.ch8"]);
+    assert!(launcher.entries[0].is_some());
+    assert!(launcher.thumbnails[0][(0, 0)]);
+}
+
+#[test]
+fn scans_roms_only() {
+    let dir = std::env::temp_dir().join(format!("chip8-launcher-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("b.ch8"), DRAW).unwrap();
+    std::fs::write(dir.join("a.CH8"), [0x12, 0x00]).unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a rom").unwrap();
+    let launcher = Launcher::scan(&dir, None);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let launcher = launcher.unwrap();
+    assert_eq!(launcher.titles, ["a.CH8", "b.ch8"]);
+    assert!(Launcher::scan("tests/golden", None).is_err());
+}
+
+#[test]
+fn keeps_the_selection_in_view() {
+    let roms = (0..30)
+        .map(|i| rom(&format!("{}.ch8", i), &[0x12, 0x00]))
+        .collect();
+    let launcher = Launcher::new(roms, None);
+    assert_eq!(launcher.first_row(0, 10), 0);
+    assert_eq!(launcher.first_row(12, 10), 7);
+    assert_eq!(launcher.first_row(29, 10), 20);
+}
+
+#[test]
+fn writes_text_with_the_font() {
+    assert_eq!(glyph('a'), FONT[50..55]);
+    assert_eq!(glyph('7'), FONT[35..40]);
+    assert_eq!(glyph('~'), glyph('?'));
+    assert_ne!(glyph('O'), glyph('0'));
+}
@@ -0,0 +1,97 @@
+use chip8::chip8::lint::{lint, Lint, Report, Severity};
+use chip8::chip8::platform::Platform;
+use chip8::chip8::program::Program;
+
+fn lint_words(words: &[u16]) -> Report {
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    lint(&Program::from(bytes))
+}
+
+// (address, lint) of every finding
+fn findings(report: &Report) -> Vec<(u16, Lint)> {
+    report
+        .findings
+        .iter()
+        .map(|f| (f.addr, f.lint.clone()))
+        .collect()
+}
+
+#[test]
+fn reports_reachable_unknown_opcodes_only() {
+    // JP 204 over an unknown opcode, then another one
+    let report = lint_words(&[0x1204, 0xFFFF, 0xE1FF]);
+    assert_eq!(findings(&report), [(0x204, Lint::UnknownOpcode)]);
+    assert_eq!(report.instructions, 2);
+    assert!(report.has_errors());
+    assert_eq!(report.findings[0].lint.severity(), Severity::Error);
+}
+
+#[test]
+fn reports_machine_code_calls() {
+    let report = lint_words(&[0x0345, 0x1202]);
+    assert_eq!(findings(&report), [(0x200, Lint::SysCall(0x345))]);
+    assert!(!report.has_errors());
+}
+
+#[test]
+fn lists_the_quirks_relied_on() {
+    let report = lint_words(&[
+        0x8121, // OR V1, V2
+        0x8126, // SHR V1, V2
+        0xA300, // LD I, 300
+        0xF155, // LD [I], V1
+        0xB210, // JP V0, 210
+    ]);
+    let quirks: Vec<&str> = report.quirks.iter().copied().collect();
+    assert_eq!(quirks, ["jumping", "memory", "shifting", "vf_reset"]);
+    assert_eq!(report.platform, Platform::Chip8);
+
+    // nothing depends on a quirk
+    let report = lint_words(&[0x6001, 0x1202]);
+    assert!(report.quirks.is_empty());
+}
+
+#[test]
+fn guesses_the_platform_from_extension_opcodes() {
+    // SCHIP HIGH, then a 16x16 sprite
+    let report = lint_words(&[0x00FF, 0xD010, 0x1204]);
+    assert_eq!(report.platform, Platform::SuperChip);
+    assert!(findings(&report).contains(&(0x200, Lint::Extension(Platform::SuperChip))));
+
+    // XO-CHIP saves V1 to V2
+    let report = lint_words(&[0x00FF, 0x5122, 0x1204]);
+    assert_eq!(report.platform, Platform::XoChip);
+    assert!(findings(&report).contains(&(0x202, Lint::Extension(Platform::XoChip))));
+    assert!(!report.has_errors());
+}
+
+#[test]
+fn reports_writes_into_code() {
+    // LD I, 202 then LD B, V0 over the instruction at 202
+    let report = lint_words(&[0xA202, 0xF033, 0x1204]);
+    assert_eq!(findings(&report), [(0x202, Lint::SelfModifying(0x202))]);
+    assert_eq!(report.findings[0].lint.severity(), Severity::Warning);
+
+    // the data after the code can be written
+    let report = lint_words(&[0xA206, 0xF033, 0x1204, 0x0000]);
+    assert!(findings(&report).is_empty());
+}
+
+#[test]
+fn reports_reads_past_the_end_of_memory() {
+    // LD I, FFE then a sprite of 5 rows
+    let report = lint_words(&[0xAFFE, 0xD015, 0x1204]);
+    assert_eq!(findings(&report), [(0x202, Lint::ReadPastEnd(0x1003))]);
+
+    // I is unknown after a subroutine, nothing is reported
+    let report = lint_words(&[0xAFFE, 0x2206, 0xD015, 0x00EE]);
+    assert!(findings(&report).is_empty());
+}
+
+#[test]
+fn handles_empty_programs() {
+    let report = lint_words(&[]);
+    assert_eq!(report.instructions, 0);
+    assert!(report.findings.is_empty());
+    assert_eq!(report.platform, Platform::Chip8);
+}
@@ -0,0 +1,98 @@
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::machine::{
+    Clock, Headless, InputSource, Machine, Parts, VideoSink, AZERTY, QWERTY,
+};
+use chip8::chip8::program::Program;
+use chip8::screen::Screen;
+
+// Counts the pixels on at each frame and the FX0A notifications
+#[derive(Default)]
+struct Recorder {
+    pixels: Vec<usize>,
+    waiting: Vec<bool>,
+}
+
+impl VideoSink for Recorder {
+    fn present(&mut self, screen: &Screen) {
+        self.pixels.push(screen.to_string().matches('X').count());
+    }
+
+    fn waiting_for_key(&mut self, waiting: bool) {
+        self.waiting.push(waiting);
+    }
+}
+
+struct Frames(u32);
+
+impl InputSource for Frames {
+    fn poll(&mut self, _keys: &mut [bool; 16]) -> bool {
+        self.0 = self.0.saturating_sub(1);
+        self.0 > 0
+    }
+}
+
+#[derive(Default)]
+struct Ticks(u32);
+
+impl Clock for Ticks {
+    fn wait_frame(&mut self) {
+        self.0 += 1;
+    }
+}
+
+fn interpreter(rom: &[u8]) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program(&Program::from(rom.to_vec())).unwrap();
+    interpreter
+}
+
+#[test]
+fn frames_go_through_every_part() {
+    // wait for a key, draw the 0 glyph, then loop
+    let rom = [0xF0, 0x0A, 0xD0, 0x05, 0x12, 0x04];
+    let frontend = Parts {
+        video: Recorder::default(),
+        input: Frames(5),
+        audio: (),
+        clock: Ticks::default(),
+    };
+    let mut machine = Machine::new(interpreter(&rom), frontend);
+    machine.frame();
+    machine.interpreter.cpu.keys[1] = true;
+    machine.frame();
+    machine.interpreter.cpu.keys[1] = false;
+    machine.run();
+
+    let frontend = machine.frontend;
+    assert_eq!(frontend.clock.0, 4);
+    assert_eq!(frontend.video.waiting, [true, false]);
+    // the release ends FX0A, DXYN then waits for the next frame
+    assert_eq!(frontend.video.pixels, [0, 0, 0, 14]);
+}
+
+#[test]
+fn headless_plays_the_keys_and_counts_beeps() {
+    // ST = 5 when key 2 is held
+    let rom = [0x62, 0x02, 0x63, 0x05, 0xE2, 0xA1, 0xF3, 0x18, 0x12, 0x04];
+    let mut headless = Headless::new(10);
+    headless.keys = vec![(2, 2, true), (3, 2, false)];
+    let mut machine = Machine::new(interpreter(&rom), headless);
+    machine.run();
+    // the timers tick at the end of the frame setting ST
+    assert_eq!(machine.frontend.beeps, 4);
+}
+
+#[test]
+fn keymaps_follow_the_vip_keypad() {
+    let rows = [
+        [0x1, 0x2, 0x3, 0xC],
+        [0x4, 0x5, 0x6, 0xD],
+        [0x7, 0x8, 0x9, 0xE],
+        [0xA, 0x0, 0xB, 0xF],
+    ];
+    let keys = |keymap: [char; 16]| {
+        rows.map(|row| row.iter().map(|&k| keymap[k]).collect::<String>())
+    };
+    assert_eq!(keys(AZERTY), ["1234", "azer", "qsdf", "wxcv"]);
+    assert_eq!(keys(QWERTY), ["1234", "qwer", "asdf", "zxcv"]);
+}
@@ -0,0 +1,573 @@
+mod common;
+
+use chip8::chip8::interpreter::Error;
+use chip8::chip8::platform::{Platform, Quirks};
+use chip8::chip8::sys::{Routines, SysPolicy};
+use common::{execute, CpuBuilder};
+use rand::RngCore;
+
+fn quirks(f: impl FnOnce(&mut Quirks)) -> Quirks {
+    let mut quirks = Platform::Chip8.quirks();
+    f(&mut quirks);
+    quirks
+}
+
+// 0
+
+#[test]
+fn cls_clears_the_screen() {
+    CpuBuilder::new()
+        .pixel(0, 0)
+        .pixel(63, 31)
+        .execute(0x00E0)
+        .pixels_on(0)
+        .pc(0x202);
+}
+
+#[test]
+fn ret_pops_the_return_address() {
+    CpuBuilder::new()
+        .stack(&[0x300, 0x400])
+        .execute(0x00EE)
+        .pc(0x400)
+        .stack(&[0x300]);
+}
+
+#[test]
+#[should_panic]
+fn sys_panics_by_default() {
+    CpuBuilder::new().execute(0x0123);
+}
+
+#[test]
+fn sys_policies() {
+    CpuBuilder::new()
+        .sys(SysPolicy::Ignore)
+        .pixel(0, 0)
+        .execute(0x0123)
+        .pc(0x202)
+        .pixels_on(1);
+
+    let routines = Routines::parse("# clears the screen\n123 clear\n0x200 scroll-down").unwrap();
+    CpuBuilder::new()
+        .sys(SysPolicy::Hle(routines.clone()))
+        .pixel(0, 0)
+        .execute(0x0123)
+        .pc(0x202)
+        .pixels_on(0);
+    CpuBuilder::new()
+        .sys(SysPolicy::Hle(routines.clone()))
+        .pixel(5, 0)
+        .pixel(5, 31)
+        .execute(0x0200)
+        .pixel(5, 1, true)
+        .pixel(5, 0, false)
+        .pixels_on(1);
+
+    let mut interpreter = CpuBuilder::new()
+        .sys(SysPolicy::Hle(routines))
+        .memory(0x200, &[0x03, 0x45])
+        .build();
+    assert_eq!(interpreter.try_step(), Err(Error::MachineCode(0x345)));
+}
+
+#[test]
+fn sys_routines_report_bad_lines() {
+    assert!(Routines::parse("123").is_err());
+    assert!(Routines::parse("1000 clear").is_err());
+    assert!(Routines::parse("123 launch-missiles").is_err());
+}
+
+// 1, 2
+
+#[test]
+fn jp_addr() {
+    CpuBuilder::new().execute(0x1234).pc(0x234);
+}
+
+#[test]
+fn call_pushes_the_next_instruction() {
+    CpuBuilder::new()
+        .stack(&[0x300])
+        .execute(0x2345)
+        .pc(0x345)
+        .stack(&[0x300, 0x202]);
+}
+
+// 3, 4, 5, 9
+
+#[test]
+fn skips_on_byte() {
+    CpuBuilder::new()
+        .register(1, 0x42)
+        .execute(0x3142)
+        .pc(0x204);
+    CpuBuilder::new()
+        .register(1, 0x41)
+        .execute(0x3142)
+        .pc(0x202);
+    CpuBuilder::new()
+        .register(1, 0x41)
+        .execute(0x4142)
+        .pc(0x204);
+    CpuBuilder::new()
+        .register(1, 0x42)
+        .execute(0x4142)
+        .pc(0x202);
+}
+
+#[test]
+fn skips_on_register() {
+    let equal = || CpuBuilder::new().register(1, 7).register(2, 7);
+    let different = || CpuBuilder::new().register(1, 7).register(2, 8);
+    equal().execute(0x5120).pc(0x204);
+    different().execute(0x5120).pc(0x202);
+    equal().execute(0x9120).pc(0x202);
+    different().execute(0x9120).pc(0x204);
+    // the last nibble is ignored
+    equal().execute(0x5121).pc(0x204);
+    equal().execute(0x912F).pc(0x202);
+}
+
+// 6, 7
+
+#[test]
+fn ld_vx_byte() {
+    CpuBuilder::new()
+        .execute(0x6A5B)
+        .register(0xA, 0x5B)
+        .pc(0x202);
+}
+
+#[test]
+fn add_vx_byte_wraps_without_touching_the_flag() {
+    CpuBuilder::new()
+        .register(1, 0xFF)
+        .register(0xF, 0xAA)
+        .execute(0x7102)
+        .register(1, 0x01)
+        .flag(0xAA);
+}
+
+// 8
+
+#[test]
+fn ld_vx_vy() {
+    CpuBuilder::new()
+        .register(2, 0x12)
+        .execute(0x8120)
+        .register(1, 0x12);
+}
+
+#[test]
+fn logic_ops_and_the_vf_reset_quirk() {
+    for (op, vy, result) in [
+        (0x8121, 0xF0, 0xFF),
+        (0x8122, 0xFF, 0x0F),
+        (0x8123, 0xFF, 0xF0),
+    ] {
+        let state = |vf_reset| {
+            CpuBuilder::new()
+                .quirks(quirks(|q| q.vf_reset = vf_reset))
+                .register(1, 0x0F)
+                .register(2, vy)
+                .register(0xF, 0xAA)
+        };
+        state(true).execute(op).register(1, result).flag(0);
+        state(false).execute(op).register(1, result).flag(0xAA);
+    }
+}
+
+#[test]
+fn add_vx_vy_sets_the_carry() {
+    let add = |a, b| {
+        CpuBuilder::new()
+            .register(1, a)
+            .register(2, b)
+            .execute(0x8124)
+    };
+    add(0x10, 0x20).register(1, 0x30).flag(0);
+    add(0xFF, 0x01).register(1, 0x00).flag(1);
+    add(0xF0, 0x20).register(1, 0x10).flag(1);
+    add(0x80, 0x7F).register(1, 0xFF).flag(0);
+}
+
+#[test]
+fn sub_vx_vy_clears_the_flag_on_borrow() {
+    let sub = |a, b| {
+        CpuBuilder::new()
+            .register(1, a)
+            .register(2, b)
+            .execute(0x8125)
+    };
+    sub(0x30, 0x10).register(1, 0x20).flag(1);
+    sub(0x10, 0x30).register(1, 0xE0).flag(0);
+    sub(0x10, 0x10).register(1, 0x00).flag(1);
+    sub(0x00, 0xFF).register(1, 0x01).flag(0);
+}
+
+#[test]
+fn subn_vx_vy_clears_the_flag_on_borrow() {
+    let subn = |a, b| {
+        CpuBuilder::new()
+            .register(1, a)
+            .register(2, b)
+            .execute(0x8127)
+    };
+    subn(0x10, 0x30).register(1, 0x20).flag(1);
+    subn(0x30, 0x10).register(1, 0xE0).flag(0);
+    subn(0x10, 0x10).register(1, 0x00).flag(1);
+}
+
+#[test]
+fn shr_and_the_shifting_quirk() {
+    let shr = |shifting| {
+        CpuBuilder::new()
+            .quirks(quirks(|q| q.shifting = shifting))
+            .register(1, 0x03)
+            .register(2, 0x80)
+            .execute(0x8126)
+    };
+    shr(false).register(1, 0x40).register(2, 0x80).flag(0);
+    shr(true).register(1, 0x01).register(2, 0x80).flag(1);
+}
+
+#[test]
+fn shl_and_the_shifting_quirk() {
+    let shl = |shifting| {
+        CpuBuilder::new()
+            .quirks(quirks(|q| q.shifting = shifting))
+            .register(1, 0x81)
+            .register(2, 0x41)
+            .execute(0x812E)
+    };
+    shl(false).register(1, 0x82).register(2, 0x41).flag(0);
+    shl(true).register(1, 0x02).register(2, 0x41).flag(1);
+}
+
+#[test]
+fn the_flag_wins_when_vf_is_the_destination() {
+    let vf = |a, b, op| {
+        CpuBuilder::new()
+            .register(0xF, a)
+            .register(2, b)
+            .execute(op)
+    };
+    vf(0xF0, 0x20, 0x8F24).flag(1);
+    vf(0x10, 0x20, 0x8F24).flag(0);
+    vf(0x10, 0x30, 0x8F25).flag(0);
+    vf(0x30, 0x10, 0x8F25).flag(1);
+    vf(0x30, 0x10, 0x8F27).flag(0);
+    vf(0x00, 0x01, 0x8F26).flag(1);
+    vf(0x00, 0x80, 0x8F2E).flag(1);
+}
+
+// A, B, C
+
+#[test]
+fn ld_i_addr() {
+    CpuBuilder::new().execute(0xA123).index(0x123);
+}
+
+#[test]
+fn jp_v0_addr_and_the_jumping_quirk() {
+    let jp = |jumping| {
+        CpuBuilder::new()
+            .quirks(quirks(|q| q.jumping = jumping))
+            .register(0, 0x10)
+            .register(3, 0x20)
+            .execute(0xB300)
+    };
+    jp(false).pc(0x310);
+    jp(true).pc(0x320);
+}
+
+#[test]
+fn rnd_is_masked_by_the_byte() {
+    CpuBuilder::new()
+        .register(1, 0xFF)
+        .execute(0xC100)
+        .register(1, 0);
+    for _ in 0..32 {
+        let expect = CpuBuilder::new().execute(0xC10F);
+        assert_eq!(expect.interpreter.cpu.registers[1] & 0xF0, 0);
+    }
+}
+
+// A generator always returning the same bytes, like a hardware RNG stub
+struct Constant(u8);
+
+impl RngCore for Constant {
+    fn next_u32(&mut self) -> u32 {
+        u32::from_ne_bytes([self.0; 4])
+    }
+    fn next_u64(&mut self) -> u64 {
+        u64::from_ne_bytes([self.0; 8])
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(self.0)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[test]
+fn rnd_uses_the_injected_rng() {
+    let mut interpreter = CpuBuilder::new().build();
+    interpreter.set_rng(Constant(0xA5));
+    execute(&mut interpreter, 0xC13C);
+    assert_eq!(interpreter.cpu.registers[1], 0x24);
+}
+
+// D
+
+#[test]
+fn drw_draws_a_sprite_at_vx_vy() {
+    // the 0 of the font : F0 90 90 90 F0
+    CpuBuilder::new()
+        .register(1, 8)
+        .register(2, 4)
+        .index(0)
+        .execute(0xD125)
+        .pixels_on(14)
+        .pixel(8, 4, true)
+        .pixel(11, 4, true)
+        .pixel(12, 4, false)
+        .pixel(9, 5, false)
+        .flag(0);
+}
+
+#[test]
+fn drw_sets_the_flag_on_collision() {
+    let mut expect = CpuBuilder::new().index(0).execute(0xD015);
+    expect.interpreter.cpu.program_counter = 0x200;
+    execute(&mut expect.interpreter, 0xD015);
+    expect.pixels_on(0).flag(1);
+}
+
+#[test]
+fn drw_wraps_the_starting_position() {
+    CpuBuilder::new()
+        .register(1, 64 + 2)
+        .register(2, 32 + 3)
+        .index(0)
+        .execute(0xD121)
+        .pixel(2, 3, true)
+        .pixels_on(4);
+}
+
+#[test]
+fn drw_clips_or_wraps_at_the_edges() {
+    let drw = |clipping| {
+        CpuBuilder::new()
+            .quirks(quirks(|q| q.clipping = clipping))
+            .register(1, 62)
+            .register(2, 30)
+            .index(0)
+            .execute(0xD125)
+    };
+    drw(true)
+        .pixels_on(3)
+        .pixel(62, 31, true)
+        .pixel(63, 31, false);
+    drw(false)
+        .pixels_on(14)
+        .pixel(0, 30, true)
+        .pixel(1, 0, true)
+        .pixel(0, 0, false);
+}
+
+// E
+
+#[test]
+fn skp_and_sknp_use_the_low_nibble() {
+    CpuBuilder::new()
+        .register(1, 0x15)
+        .key(5)
+        .execute(0xE19E)
+        .pc(0x204);
+    CpuBuilder::new().register(1, 5).execute(0xE19E).pc(0x202);
+    CpuBuilder::new()
+        .register(1, 5)
+        .key(5)
+        .execute(0xE1A1)
+        .pc(0x202);
+    CpuBuilder::new()
+        .register(1, 0x25)
+        .execute(0xE1A1)
+        .pc(0x204);
+}
+
+// F
+
+#[test]
+fn timers() {
+    CpuBuilder::new()
+        .delay_timer(9)
+        .execute(0xF107)
+        .register(1, 9);
+    CpuBuilder::new()
+        .register(1, 7)
+        .execute(0xF115)
+        .delay_timer(7);
+    CpuBuilder::new()
+        .register(1, 7)
+        .execute(0xF118)
+        .sound_timer(7);
+}
+
+#[test]
+fn ld_vx_k_waits_for_a_press_and_release() {
+    let mut expect = CpuBuilder::new().key(3).execute(0xF20A).pc(0x200);
+    // 3 was held before the wait started, it has to be released first
+    expect.interpreter.step();
+    expect.interpreter.cpu.keys[3] = false;
+    expect.interpreter.step();
+    expect.interpreter.cpu.keys[7] = true;
+    expect.interpreter.step();
+    let mut expect = expect.pc(0x200);
+    expect.interpreter.cpu.keys[7] = false;
+    expect.interpreter.step();
+    expect.pc(0x202).register(2, 7);
+}
+
+#[test]
+fn add_i_vx() {
+    CpuBuilder::new()
+        .index(0x100)
+        .register(1, 0x20)
+        .execute(0xF11E)
+        .index(0x120)
+        .flag(0);
+}
+
+#[test]
+fn ld_f_vx_points_to_the_digit() {
+    CpuBuilder::new().register(1, 0xA).execute(0xF129).index(50);
+    CpuBuilder::new()
+        .register(1, 0x1F)
+        .execute(0xF129)
+        .index(75);
+}
+
+#[test]
+fn memory_accesses_wrap_around_at_4k() {
+    CpuBuilder::new()
+        .index(0xFFFF)
+        .register(1, 1)
+        .execute(0xF11E)
+        .index(0);
+    CpuBuilder::new()
+        .register(1, 123)
+        .index(0xFFF)
+        .execute(0xF133)
+        .memory(0xFFF, &[1])
+        .memory(0, &[2, 3]);
+    CpuBuilder::new()
+        .register(0, 1)
+        .register(1, 2)
+        .register(2, 3)
+        .index(0xFFE)
+        .execute(0xF255)
+        .memory(0xFFE, &[1, 2])
+        .memory(0, &[3])
+        .index(0x1001);
+    CpuBuilder::new()
+        .memory(0xFFF, &[7])
+        .memory(0, &[8])
+        .index(0xFFF)
+        .execute(0xF165)
+        .register(0, 7)
+        .register(1, 8);
+    // a sprite read across the end
+    CpuBuilder::new()
+        .memory(0xFFF, &[0x80])
+        .memory(0, &[0x80])
+        .index(0xFFF)
+        .execute(0xD012)
+        .pixel(0, 0, true)
+        .pixel(0, 1, true)
+        .pixels_on(2);
+}
+
+#[test]
+fn ld_b_vx_stores_the_decimal_digits() {
+    for (value, digits) in [
+        (0, [0, 0, 0]),
+        (9, [0, 0, 9]),
+        (10, [0, 1, 0]),
+        (100, [1, 0, 0]),
+        (255, [2, 5, 5]),
+    ] {
+        CpuBuilder::new()
+            .register(1, value)
+            .index(0x300)
+            .execute(0xF133)
+            .memory(0x300, &digits)
+            .index(0x300);
+    }
+}
+
+#[test]
+fn ld_i_vx_and_the_memory_quirk() {
+    let ld = |memory| {
+        CpuBuilder::new()
+            .quirks(quirks(|q| q.memory = memory))
+            .register(0, 1)
+            .register(1, 2)
+            .register(2, 3)
+            .register(3, 4)
+            .index(0x300)
+            .execute(0xF255)
+    };
+    ld(true).memory(0x300, &[1, 2, 3, 0]).index(0x303);
+    ld(false).memory(0x300, &[1, 2, 3, 0]).index(0x300);
+}
+
+#[test]
+fn ld_vx_i_and_the_memory_quirk() {
+    let ld = |memory| {
+        CpuBuilder::new()
+            .quirks(quirks(|q| q.memory = memory))
+            .memory(0x300, &[1, 2, 3, 4])
+            .index(0x300)
+            .execute(0xF265)
+    };
+    ld(true)
+        .register(0, 1)
+        .register(2, 3)
+        .register(3, 0)
+        .index(0x303);
+    ld(false)
+        .register(0, 1)
+        .register(2, 3)
+        .register(3, 0)
+        .index(0x300);
+}
+
+// try_step
+
+#[test]
+fn try_step_reports_instead_of_panicking() {
+    let mut interpreter = CpuBuilder::new().memory(0x200, &[0x00, 0xEE]).build();
+    assert_eq!(interpreter.try_step(), Err(Error::StackUnderflow));
+    assert_eq!(interpreter.cpu.program_counter, 0x200);
+
+    let mut interpreter = CpuBuilder::new()
+        .stack(&[0x300; 15])
+        .memory(0x200, &[0x22, 0x00])
+        .build();
+    assert_eq!(interpreter.try_step(), Err(Error::StackOverflow));
+
+    let mut interpreter = CpuBuilder::new().memory(0x200, &[0xFF, 0xFF]).build();
+    assert_eq!(interpreter.try_step(), Err(Error::UnknownOpcode(0xFFFF)));
+
+    let mut interpreter = CpuBuilder::new().pc(0xFFF).build();
+    assert_eq!(interpreter.try_step(), Err(Error::PcOutOfBounds(0xFFF)));
+
+    let mut interpreter = CpuBuilder::new().memory(0x200, &[0x60, 0x01]).build();
+    assert_eq!(interpreter.try_step(), Ok(()));
+    assert_eq!(interpreter.cpu.registers[0], 1);
+}
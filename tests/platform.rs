@@ -0,0 +1,82 @@
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::platform::{Platform, Quirks};
+
+const PLATFORMS: [Platform; 3] = [Platform::Chip8, Platform::SuperChip, Platform::XoChip];
+
+#[test]
+fn names_round_trip() {
+    for platform in PLATFORMS {
+        assert_eq!(Platform::from_name(platform.name()), Some(platform));
+        assert_eq!(platform.to_string(), platform.name());
+    }
+    assert_eq!(Platform::from_name("vip"), Some(Platform::Chip8));
+    assert_eq!(Platform::from_name("superchip"), Some(Platform::SuperChip));
+    assert_eq!(Platform::from_name("megachip"), None);
+}
+
+#[test]
+fn presets_follow_the_quirks_test() {
+    assert_eq!(
+        Platform::Chip8.quirks().to_string(),
+        "vf_reset=on memory=on display_wait=on clipping=on shifting=off jumping=off"
+    );
+    assert_eq!(
+        Platform::SuperChip.quirks().to_string(),
+        "vf_reset=off memory=off display_wait=off clipping=on shifting=on jumping=on"
+    );
+    assert_eq!(
+        Platform::XoChip.quirks().to_string(),
+        "vf_reset=off memory=on display_wait=off clipping=off shifting=off jumping=off"
+    );
+}
+
+#[test]
+fn interpreters_start_with_the_vip_quirks() {
+    assert_eq!(Interpreter::new().quirks, Platform::Chip8.quirks());
+}
+
+#[test]
+fn tells_the_platform_of_extension_opcodes() {
+    let cases = [
+        (0x00E0, None),
+        (0x0123, None),
+        (0x00C4, Some(Platform::SuperChip)),
+        (0x00FF, Some(Platform::SuperChip)),
+        (0x00D2, Some(Platform::XoChip)),
+        (0x5120, None),
+        (0x5122, Some(Platform::XoChip)),
+        (0x5123, Some(Platform::XoChip)),
+        (0xD120, Some(Platform::SuperChip)),
+        (0xD125, None),
+        (0xF130, Some(Platform::SuperChip)),
+        (0xF175, Some(Platform::SuperChip)),
+        (0xF000, Some(Platform::XoChip)),
+        (0xF100, None),
+        (0xF201, Some(Platform::XoChip)),
+        (0xF13A, Some(Platform::XoChip)),
+        (0xF002, Some(Platform::XoChip)),
+        (0xF129, None),
+    ];
+    for (op, platform) in cases {
+        assert_eq!(Platform::of_opcode(op), platform, "{:04X}", op);
+    }
+}
+
+#[test]
+fn quirks_update_from_names() {
+    let mut updated = Platform::Chip8.quirks();
+    updated.update("vf_reset=off,shifting=on jumping").unwrap();
+    let expected = Quirks {
+        vf_reset: false,
+        shifting: true,
+        jumping: true,
+        ..Platform::Chip8.quirks()
+    };
+    assert_eq!(updated, expected);
+
+    assert_eq!(
+        Platform::Chip8.quirks().update("wrap=on"),
+        Err("unknown quirk wrap".to_string())
+    );
+    assert!(Platform::Chip8.quirks().update("clipping=maybe").is_err());
+}
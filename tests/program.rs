@@ -0,0 +1,81 @@
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::platform::Platform;
+use chip8::chip8::program::{LoadError, Program};
+
+#[test]
+fn loads_bytes_and_readers() {
+    let program = Program::from_bytes(&[0x12, 0x00]).unwrap();
+    assert_eq!(program.address, 0x200);
+    assert_eq!(program.content, [0x12, 0x00]);
+
+    let program = Program::from_reader(&[0x60, 0x01][..]).unwrap();
+    assert_eq!(program.content, [0x60, 0x01]);
+}
+
+#[test]
+fn rejects_programs_larger_than_memory() {
+    let error = Program::from_bytes(&[0; 4096]).err().unwrap();
+    assert!(matches!(
+        error,
+        LoadError::TooLarge {
+            size: 4096,
+            max: 0xE00
+        }
+    ));
+    assert!(Program::from_reader(&[0u8; 10000][..]).is_err());
+    assert!(Program::from_bytes(&[0; 0xE00]).is_ok());
+}
+
+#[test]
+fn reports_missing_files() {
+    let error = Program::from_path("tests/roms/missing.ch8").err().unwrap();
+    assert!(matches!(error, LoadError::Io(_)));
+}
+
+#[test]
+fn checks_the_platform_memory() {
+    let program = Program::from_bytes(&[0; 0xD00]).unwrap();
+    assert!(program.check(Platform::Chip8).is_err());
+    assert!(program.check(Platform::SuperChip).is_ok());
+    assert!(program.check(Platform::XoChip).is_ok());
+    assert!(Program::from_bytes(&[0; 0xCA0])
+        .unwrap()
+        .check(Platform::Chip8)
+        .is_ok());
+}
+
+#[test]
+fn loads_at_another_address() {
+    let program = Program::from_bytes(&[0x70, 0x05])
+        .unwrap()
+        .at(0x600)
+        .unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program(&program).unwrap();
+    assert_eq!(interpreter.cpu.program_counter, 0x600);
+    interpreter.try_step().unwrap();
+    assert_eq!(interpreter.cpu.registers[0], 5);
+
+    assert!(Program::from_bytes(&[0; 0xA01]).unwrap().at(0x600).is_err());
+    assert!(Program::from(vec![0]).at(0x1000).is_err());
+}
+
+#[test]
+fn load_program_does_not_panic() {
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.load_program(&Program::from(vec![0; 5000]));
+    assert!(matches!(error, Err(LoadError::TooLarge { .. })));
+}
+
+#[test]
+fn hashes_the_content() {
+    let program = Program::from_bytes(b"abc").unwrap();
+    assert_eq!(
+        program.sha1_hex(),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(
+        Program::from_bytes(b"123456789").unwrap().crc32(),
+        0xCBF43926
+    );
+}
@@ -0,0 +1,173 @@
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::program::Program;
+use chip8::chip8::recompiler::recompile;
+use std::{env, fs, path::Path};
+
+// tests/golden/maze.rs is the recompiled MAZE, checked by recompiles_the_maze
+mod maze {
+    include!("golden/maze.rs");
+}
+
+// Draws a maze of random diagonals, counts the screens in BCD and waits on the delay timer
+const MAZE: [u16; 35] = [
+    0xA23C, // 200  LD I, 23C
+    0xC001, // 202  RND V0, 1
+    0x3001, // 204  SE V0, 1
+    0xA240, // 206  LD I, 240
+    0xD124, // 208  DRW V1, V2, 4
+    0x7104, // 20A  ADD V1, 4
+    0x3140, // 20C  SE V1, 40
+    0x1200, // 20E  JP 200
+    0x6100, // 210  LD V1, 0
+    0x7204, // 212  ADD V2, 4
+    0x3220, // 214  SE V2, 20
+    0x1200, // 216  JP 200
+    0x2230, // 218  CALL 230
+    0x6405, // 21A  LD V4, 5
+    0xF415, // 21C  LD DT, V4
+    0xF407, // 21E  LD V4, DT
+    0x3400, // 220  SE V4, 0
+    0x121E, // 222  JP 21E
+    0x00E0, // 224  CLS
+    0x6100, // 226  LD V1, 0
+    0x6200, // 228  LD V2, 0
+    0x1200, // 22A  JP 200
+    0x0000, // 22C
+    0x0000, // 22E
+    0x7301, // 230  ADD V3, 1
+    0xA244, // 232  LD I, 244
+    0xF333, // 234  LD B, V3
+    0xF265, // 236  LD V2, [I]
+    0x00EE, // 238  RET
+    0x0000, // 23A
+    0x8040, // 23C  sprite \
+    0x2010, //
+    0x1020, // 240  sprite /
+    0x4080, //
+    0x0000, // 244  the BCD of V3
+];
+
+fn program(words: &[u16]) -> Program {
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    Program::from(bytes)
+}
+
+fn recompile_words(words: &[u16]) -> String {
+    recompile(&program(words), "test.ch8")
+}
+
+#[test]
+fn ends_blocks_after_memory_writes() {
+    let rs = recompile_words(&[
+        0x6001, // 200  LD V0, 1
+        0xA202, // 202  LD I, 202
+        0xF033, // 204  LD B, V0
+        0x7001, // 206  ADD V0, 1
+        0xF055, // 208  LD [I], V0
+        0x7002, // 20A  ADD V0, 2
+        0x120A, // 20C  JP 20A
+    ]);
+    assert!(rs.contains("        0x200 => (0x200, 0x206, 3, block_200),\n"));
+    assert!(rs.contains("        0x206 => (0x206, 0x20A, 2, block_206),\n"));
+    assert!(rs.contains("        0x20A => (0x20A, 0x20E, 2, block_20a),\n"));
+    // the instructions after a write are compared to the rom again
+    assert!(rs.contains(
+        "    // 204: LD B, V0\n    i.cpu.program_counter = 0x206;\n    i.ld_b_vx(0);\n    0x206\n}\n"
+    ));
+}
+
+#[test]
+fn ends_blocks_after_a_key_wait() {
+    let rs = recompile_words(&[
+        0x6001, // 200  LD V0, 1
+        0xF10A, // 202  LD V1, K
+        0x7101, // 204  ADD V1, 1
+        0x1200, // 206  JP 200
+    ]);
+    // FX0A runs again while no key is pressed, the block reports 2 steps
+    assert!(rs.contains("        0x200 => (0x200, 0x204, 2, block_200),\n"));
+    assert!(rs.contains("        0x204 => (0x204, 0x208, 2, block_204),\n"));
+}
+
+#[test]
+fn ends_blocks_after_unknown_opcodes() {
+    let rs = recompile_words(&[
+        0x00FF, // 200  HIGH, not an instruction of the interpreter
+        0x6001, // 202  LD V0, 1
+        0x1202, // 204  JP 202
+    ]);
+    assert!(rs.contains("        0x200 => (0x200, 0x202, 1, block_200),\n"));
+    assert!(rs.contains("        0x202 => (0x202, 0x206, 2, block_202),\n"));
+}
+
+#[test]
+fn starts_blocks_at_sprites() {
+    let rs = recompile_words(&[
+        0x6001, // 200  LD V0, 1
+        0xD005, // 202  DRW V0, V0, 5
+        0x7001, // 204  ADD V0, 1
+        0x1200, // 206  JP 200
+    ]);
+    // the frame can wait for the display before the sprite
+    assert!(rs.contains("        0x200 => (0x200, 0x202, 1, block_200),\n"));
+    assert!(rs.contains("        0x202 => (0x202, 0x208, 3, block_202),\n"));
+}
+
+// Writes tests/golden/maze.rs with UPDATE_GOLDEN=1
+#[test]
+fn recompiles_the_maze() {
+    let rs = recompile(&program(&MAZE), "maze.ch8");
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/maze.rs");
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &rs).unwrap();
+        return;
+    }
+    assert!(
+        fs::read_to_string(&path).unwrap() == rs,
+        "{} differs from the recompiled maze, run with UPDATE_GOLDEN=1",
+        path.display()
+    );
+}
+
+fn assert_same(interpreter: &Interpreter, recompiled: &Interpreter, frame: u32) {
+    let (a, b) = (&interpreter.cpu, &recompiled.cpu);
+    assert_eq!(a.program_counter, b.program_counter, "frame {}", frame);
+    assert_eq!(a.registers, b.registers, "frame {}", frame);
+    assert_eq!(a.index, b.index, "frame {}", frame);
+    assert_eq!(a.stack_pointer, b.stack_pointer, "frame {}", frame);
+    assert_eq!(a.stack, b.stack, "frame {}", frame);
+    assert_eq!(a.delay_timer, b.delay_timer, "frame {}", frame);
+    assert_eq!(a.memory, b.memory, "frame {}", frame);
+    assert_eq!(
+        a.display.to_string(),
+        b.display.to_string(),
+        "frame {}",
+        frame
+    );
+}
+
+fn runs_like_the_interpreter(tickrate: u32, display_wait: bool) {
+    let mut interpreter = Interpreter::new();
+    let mut recompiled = Interpreter::new();
+    interpreter.load_program(&program(&MAZE)).unwrap();
+    maze::load(&mut recompiled);
+    for i in [&mut interpreter, &mut recompiled] {
+        i.tickrate = tickrate;
+        i.quirks.display_wait = display_wait;
+        i.seed_rng(8);
+    }
+
+    for frame in 0..300 {
+        interpreter.run_frame();
+        maze::run_frame(&mut recompiled);
+        assert_same(&interpreter, &recompiled, frame);
+    }
+    // the maze was drawn then cleared at least once
+    assert!(recompiled.cpu.registers[3] > 0);
+}
+
+#[test]
+fn runs_frames_like_the_interpreter() {
+    runs_like_the_interpreter(15, true);
+    runs_like_the_interpreter(100, false);
+}
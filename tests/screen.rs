@@ -0,0 +1,33 @@
+use chip8::screen::Screen;
+
+fn screen(pixels: &[(usize, usize)]) -> Screen {
+    let mut screen = Screen::new();
+    for &(x, y) in pixels {
+        screen[(y, x)] = true;
+    }
+    screen
+}
+
+#[test]
+fn half_blocks_pack_two_rows() {
+    let text = screen(&[(0, 0), (1, 1), (2, 0), (2, 1), (63, 31)])
+        .half_blocks()
+        .to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 16);
+    assert!(lines.iter().all(|line| line.chars().count() == 64));
+    assert!(lines[0].starts_with("▀▄█ "));
+    assert!(lines[15].ends_with(" ▄"));
+}
+
+#[test]
+fn braille_packs_two_by_four() {
+    let text = screen(&[(0, 0), (1, 3), (2, 1), (3, 2), (4, 4)])
+        .braille()
+        .to_string();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 8);
+    assert!(lines.iter().all(|line| line.chars().count() == 32));
+    assert!(lines[0].starts_with("⢁⠢⠀"));
+    assert!(lines[1].starts_with("⠀⠀⠁"));
+}
@@ -0,0 +1,176 @@
+use chip8::chip8::cpu::Cpu;
+use chip8::chip8::instructions::Instruction;
+use chip8::chip8::interpreter::{Error, Interpreter};
+use chip8::chip8::program::Program;
+use chip8::chip8::timing::{cycles, CYCLES_PER_FRAME, INTERRUPT_CYCLES};
+
+fn cycles_of(op: u16, cpu: &Cpu) -> i32 {
+    cycles(&Instruction::decode(op).unwrap(), cpu)
+}
+
+#[test]
+fn costs_vip_machine_cycles() {
+    let mut cpu = Cpu::new();
+    let cases = [
+        (0x00E0, 24), // CLS
+        (0x00EE, 10), // RET
+        (0x1200, 12), // JP 200
+        (0x2200, 26), // CALL 200
+        (0x6000, 6),  // LD V0, 0
+        (0x7001, 10), // ADD V0, 1
+        (0x8010, 12), // LD V0, V1
+        (0x8014, 44), // ADD V0, V1
+        (0xA200, 12), // LD I, 200
+        (0xB200, 22), // JP V0, 200
+        (0xF01E, 16), // ADD I, V0
+        (0xF029, 16), // LD F, V0
+        (0xF255, 58), // LD [I], V2
+    ];
+    for (op, expected) in cases {
+        assert_eq!(cycles_of(op, &cpu), expected, "{:04X}", op);
+    }
+
+    cpu.registers[0] = 0x10;
+    // skips cost 4 more when taken
+    assert_eq!(cycles_of(0x3010, &cpu), 14);
+    assert_eq!(cycles_of(0x3011, &cpu), 10);
+    assert_eq!(cycles_of(0x5010, &cpu), 14);
+    // the jump crosses from page 2 into page 3
+    assert_eq!(cycles_of(0xB2F8, &cpu), 24);
+    // a sprite on a byte boundary, then across two bytes
+    assert_eq!(cycles_of(0xD015, &cpu), 26 + 21 * 5);
+    cpu.registers[0] = 0x11;
+    assert_eq!(cycles_of(0xD015, &cpu), 26 + 35 * 5);
+    // the digits of 17
+    assert_eq!(cycles_of(0xF033, &cpu), 84 + 16 * 8);
+}
+
+// ADD I, V1 then JP 200, 28 cycles a loop
+const COUNTER: [u8; 6] = [0x61, 0x01, 0xF1, 0x1E, 0x12, 0x02];
+
+fn counter(cycle_accurate: bool) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .load_program(&Program::from(COUNTER.to_vec()))
+        .unwrap();
+    interpreter.set_cycle_accurate(cycle_accurate);
+    interpreter.step();
+    interpreter
+}
+
+#[test]
+fn runs_the_cycles_of_a_vip_frame() {
+    let mut interpreter = counter(true);
+    let budget = CYCLES_PER_FRAME - INTERRUPT_CYCLES;
+    assert_eq!(budget, 2596);
+
+    // 92 loops leave 20 cycles, the next ADD I runs then the JP past the end
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.index, 93);
+    assert_eq!(interpreter.clock.as_ref().unwrap().budget, -8);
+
+    // the 8 cycles overrun are taken from the next frame
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.index, 186);
+    assert_eq!(interpreter.cpu.program_counter, 0x204);
+    assert_eq!(interpreter.clock.as_ref().unwrap().budget, -4);
+}
+
+#[test]
+fn switches_back_to_the_tickrate() {
+    let mut interpreter = counter(true);
+    assert!(interpreter.clock.is_some());
+    interpreter.set_cycle_accurate(false);
+    assert!(interpreter.clock.is_none());
+
+    interpreter.tickrate = 10;
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.index, 5);
+}
+
+// ADD V1, 1 then a DRW every loop
+const DRAW_LOOP: [u8; 8] = [0xA0, 0x00, 0x71, 0x01, 0xD0, 0x01, 0x12, 0x02];
+
+fn draw_loop(display_wait: bool) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .load_program(&Program::from(DRAW_LOOP.to_vec()))
+        .unwrap();
+    interpreter.quirks.display_wait = display_wait;
+    interpreter.tickrate = 10;
+    interpreter.step();
+    interpreter
+}
+
+#[test]
+fn display_wait_draws_once_a_frame() {
+    let mut interpreter = draw_loop(true);
+    // the DRW waits for the next frame
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.registers[1], 1);
+    assert_eq!(interpreter.cpu.program_counter, 0x204);
+    // then runs first
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.registers[1], 2);
+    assert_eq!(interpreter.cpu.program_counter, 0x204);
+
+    let mut interpreter = draw_loop(false);
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.registers[1], 4);
+}
+
+#[test]
+fn display_wait_loses_the_rest_of_a_vip_frame() {
+    let mut interpreter = draw_loop(true);
+    interpreter.set_cycle_accurate(true);
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.registers[1], 1);
+    assert_eq!(interpreter.clock.as_ref().unwrap().budget, 0);
+    interpreter.run_frame();
+    assert_eq!(interpreter.cpu.registers[1], 2);
+}
+
+#[test]
+fn try_run_frame_waits_for_the_display_too() {
+    let mut interpreter = draw_loop(true);
+    interpreter.try_run_frame().unwrap();
+    interpreter.try_run_frame().unwrap();
+    assert_eq!(interpreter.cpu.registers[1], 2);
+
+    // an unknown opcode stops the frame
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .load_program(&Program::from(vec![0xFF, 0xFF]))
+        .unwrap();
+    assert!(interpreter.try_run_frame().is_err());
+}
+
+#[test]
+fn try_run_frame_runs_the_vip_clock() {
+    let mut interpreter = counter(true);
+    interpreter.try_run_frame().unwrap();
+    assert_eq!(interpreter.cpu.index, 93);
+    assert_eq!(interpreter.clock.as_ref().unwrap().budget, -8);
+}
+
+#[test]
+fn the_end_of_memory_is_an_error_not_a_panic() {
+    // JP FFF, the last byte of memory cannot hold an instruction
+    for cycle_accurate in [false, true] {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .load_program(&Program::from(vec![0x1F, 0xFF]))
+            .unwrap();
+        interpreter.tickrate = 10;
+        interpreter.set_cycle_accurate(cycle_accurate);
+        assert_eq!(interpreter.peek(), Some(0x1FFF));
+        assert_eq!(
+            interpreter.try_run_frame(),
+            Err(Error::PcOutOfBounds(0xFFF))
+        );
+        assert_eq!(interpreter.peek(), None);
+        assert!(!interpreter.waits_display(false));
+        // the clock is kept for the next frame
+        assert_eq!(interpreter.clock.is_some(), cycle_accurate);
+    }
+}
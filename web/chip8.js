@@ -0,0 +1,42 @@
+// Wraps the exports of chip8_web.wasm, used by index.html and by test.mjs
+export class Chip8 {
+  static async load(bytes) {
+    const { instance } = await WebAssembly.instantiate(bytes, {});
+    return new Chip8(instance.exports);
+  }
+
+  constructor(exports) {
+    this.exports = exports;
+    this.exports.chip8_seed((Math.random() * 0x100000000) >>> 0);
+  }
+
+  // false when the rom does not fit in memory
+  loadRom(rom) {
+    const pointer = this.exports.chip8_rom_buffer(rom.length);
+    new Uint8Array(this.exports.memory.buffer, pointer, rom.length).set(rom);
+    return this.exports.chip8_load(rom.length) === 0;
+  }
+
+  setTickrate(tickrate) {
+    this.exports.chip8_set_tickrate(tickrate);
+  }
+
+  setKey(key, pressed) {
+    this.exports.chip8_set_key(key, pressed ? 1 : 0);
+  }
+
+  // Runs one frame, true while the buzzer sounds, throws when the rom reaches
+  // an instruction that cannot be executed
+  runFrame() {
+    const code = this.exports.chip8_run_frame();
+    if (code < 0) {
+      throw new Error("the rom stopped on an instruction that cannot be executed");
+    }
+    return code !== 0;
+  }
+
+  // 64x32 bytes, 1 for a pixel on, row by row
+  framebuffer() {
+    return new Uint8Array(this.exports.memory.buffer, this.exports.chip8_framebuffer(), 64 * 32);
+  }
+}
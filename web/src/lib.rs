@@ -0,0 +1,115 @@
+// The API of chip8_web.wasm, plain exported functions so the page only needs
+// WebAssembly.instantiate : the page copies a rom into the buffer given by
+// chip8_rom_buffer, loads it, then calls chip8_run_frame 60 times a second and
+// draws the 64x32 bytes at chip8_framebuffer. The functions return negative
+// codes instead of panicking, a panic traps the module for good
+use chip8::chip8::interpreter::Interpreter;
+use chip8::chip8::machine::Machine;
+use chip8::chip8::program::Program;
+use std::cell::RefCell;
+
+struct State {
+    machine: Machine<()>,
+    rom: Vec<u8>,
+    // given by the page, the random generator restarts from it on every load
+    seed: Option<u64>,
+    // one byte per pixel, 0 or 1, row by row
+    framebuffer: [u8; 64 * 32],
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State {
+        machine: Machine::new(Interpreter::new(), ()),
+        rom: Vec::new(),
+        seed: None,
+        framebuffer: [0; 64 * 32],
+    });
+}
+
+// A buffer of `len` bytes in the memory of the module for the rom
+#[no_mangle]
+pub extern "C" fn chip8_rom_buffer(len: usize) -> *mut u8 {
+    STATE.with(|state| {
+        let rom = &mut state.borrow_mut().rom;
+        *rom = vec![0; len];
+        rom.as_mut_ptr()
+    })
+}
+
+// Resets the machine with the rom in the buffer, 0 on success and -1 when
+// the rom does not fit in memory
+#[no_mangle]
+pub extern "C" fn chip8_load(len: usize) -> i32 {
+    STATE.with(|state| {
+        let state = &mut *state.borrow_mut();
+        if len > state.rom.len() {
+            return -1;
+        }
+        let mut interpreter = Interpreter::new();
+        interpreter.tickrate = state.machine.interpreter.tickrate;
+        if let Some(seed) = state.seed {
+            interpreter.seed_rng(seed);
+        }
+        let program = Program::from(state.rom[..len].to_vec());
+        if interpreter.load_program(&program).is_err() {
+            return -1;
+        }
+        state.machine = Machine::new(interpreter, ());
+        state.framebuffer = [0; 64 * 32];
+        0
+    })
+}
+
+// The page passes a random seed, the module has no entropy source
+#[no_mangle]
+pub extern "C" fn chip8_seed(seed: u32) {
+    STATE.with(|state| {
+        let state = &mut *state.borrow_mut();
+        state.seed = Some(seed as u64);
+        state.machine.interpreter.seed_rng(seed as u64);
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn chip8_set_tickrate(tickrate: u32) {
+    STATE.with(|state| state.borrow_mut().machine.interpreter.tickrate = tickrate)
+}
+
+#[no_mangle]
+pub extern "C" fn chip8_set_key(key: u32, pressed: i32) {
+    STATE.with(|state| {
+        if let Some(k) = state
+            .borrow_mut()
+            .machine
+            .interpreter
+            .cpu
+            .keys
+            .get_mut(key as usize)
+        {
+            *k = pressed != 0;
+        }
+    })
+}
+
+// Runs one frame and updates the framebuffer, returns 1 while the buzzer
+// sounds and -1 when the rom reaches an instruction that cannot be executed,
+// the machine stays on it
+#[no_mangle]
+pub extern "C" fn chip8_run_frame() -> i32 {
+    STATE.with(|state| {
+        let state = &mut *state.borrow_mut();
+        if state.machine.try_frame().is_err() {
+            return -1;
+        }
+        let display = &state.machine.interpreter.cpu.display;
+        for (i, pixel) in state.framebuffer.iter_mut().enumerate() {
+            *pixel = display[(i / 64, i % 64)] as u8;
+        }
+        (state.machine.interpreter.cpu.sound_timer > 0) as i32
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn chip8_framebuffer() -> *const u8 {
+    STATE.with(|state| state.borrow().framebuffer.as_ptr())
+}
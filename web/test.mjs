@@ -0,0 +1,38 @@
+// Runs the module without a browser :
+//   cargo build --release --target wasm32-unknown-unknown
+//   node test.mjs [path/to/chip8_web.wasm]
+import { readFileSync } from "node:fs";
+import { Chip8 } from "./chip8.js";
+
+const path = process.argv[2] || new URL("target/wasm32-unknown-unknown/release/chip8_web.wasm", import.meta.url);
+const chip8 = await Chip8.load(readFileSync(path));
+
+function check(condition, message) {
+  if (!condition) {
+    console.error("FAIL", message);
+    process.exit(1);
+  }
+}
+
+// draws the 0 glyph when key 5 is held then released, sounds after
+const rom = [0xF0, 0x0A, 0x61, 0x05, 0xF1, 0x18, 0xD0, 0x05, 0x12, 0x08];
+check(chip8.loadRom(new Uint8Array(rom)), "loads the rom");
+chip8.setTickrate(10);
+check(!chip8.runFrame(), "silent while waiting for a key");
+chip8.setKey(5, true);
+chip8.runFrame();
+chip8.setKey(5, false);
+check(chip8.runFrame(), "sounds after the key");
+chip8.runFrame();
+const on = chip8.framebuffer().reduce((n, pixel) => n + pixel, 0);
+check(on === 14, `14 pixels of the 0 glyph, got ${on}`);
+check(!chip8.loadRom(new Uint8Array(4096)), "refuses a rom larger than memory");
+check(chip8.loadRom(new Uint8Array([0xFF, 0xFF])), "loads an unknown opcode");
+let threw = false;
+try {
+  chip8.runFrame();
+} catch {
+  threw = true;
+}
+check(threw, "throws on an unknown opcode");
+console.log("ok");
@@ -0,0 +1,70 @@
+use chip8_web::*;
+
+// The calls of the page, natively
+fn load(rom: &[u8]) -> i32 {
+    let buffer = chip8_rom_buffer(rom.len());
+    unsafe { std::ptr::copy_nonoverlapping(rom.as_ptr(), buffer, rom.len()) };
+    chip8_load(rom.len())
+}
+
+fn pixels() -> usize {
+    let framebuffer = unsafe { std::slice::from_raw_parts(chip8_framebuffer(), 64 * 32) };
+    framebuffer.iter().filter(|p| **p == 1).count()
+}
+
+#[test]
+fn runs_a_rom_like_the_page() {
+    // draws the 0 glyph when key 5 is held, sounds while drawing
+    let rom = [0xF0, 0x0A, 0x61, 0x05, 0xF1, 0x18, 0xD0, 0x05, 0x12, 0x08];
+    assert_eq!(load(&rom), 0);
+    chip8_seed(1);
+    chip8_set_tickrate(10);
+
+    assert_eq!(chip8_run_frame(), 0);
+    chip8_set_key(5, 1);
+    chip8_run_frame();
+    chip8_set_key(5, 0);
+    assert_eq!(chip8_run_frame(), 1);
+    chip8_run_frame();
+    assert_eq!(pixels(), 14);
+
+    assert_eq!(load(&[0; 4096]), -1);
+}
+
+#[test]
+fn errors_do_not_trap() {
+    // an unknown opcode, then a jump to the last byte of memory
+    for rom in [[0xFF, 0xFF], [0x1F, 0xFF]] {
+        assert_eq!(load(&rom), 0);
+        chip8_set_tickrate(10);
+        assert_eq!(chip8_run_frame(), -1);
+        assert_eq!(chip8_run_frame(), -1);
+    }
+    // the state is still usable
+    assert_eq!(load(&[0x12, 0x00]), 0);
+    assert_eq!(chip8_run_frame(), 0);
+}
+
+fn framebuffer() -> Vec<u8> {
+    unsafe { std::slice::from_raw_parts(chip8_framebuffer(), 64 * 32) }.to_vec()
+}
+
+#[test]
+fn the_seed_outlives_loads() {
+    // draws the 0 glyph at random places, one a frame
+    let rom = [0xC0, 0x3F, 0xC1, 0x1F, 0xD0, 0x15, 0x12, 0x00];
+    chip8_set_tickrate(30);
+    let run = |seed| {
+        // the page seeds before loading the rom
+        chip8_seed(seed);
+        assert_eq!(load(&rom), 0);
+        for _ in 0..10 {
+            chip8_run_frame();
+        }
+        framebuffer()
+    };
+    let first = run(7);
+    assert!(first.contains(&1));
+    assert!(run(7) == first);
+    assert!(run(8) != first);
+}